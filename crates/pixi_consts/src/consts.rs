@@ -11,6 +11,7 @@ pub const PROJECT_MANIFEST: &str = "pixi.toml";
 pub const PYPROJECT_MANIFEST: &str = "pyproject.toml";
 pub const PROJECT_LOCK_FILE: &str = "pixi.lock";
 pub const CONFIG_FILE: &str = "config.toml";
+pub const EXPORT_CONFIG_FILE: &str = ".pixi-export.toml";
 pub const PIXI_DIR: &str = ".pixi";
 pub const PIXI_VERSION: &str = "0.27.1";
 pub const PREFIX_FILE_NAME: &str = "pixi_env_prefix";
@@ -280,6 +280,66 @@ pub async fn execute_command(command: Command) -> miette::Result<()> {
     }
 }
 
+/// An error that makes pixi exit with a specific exit code instead of 1. The
+/// report is printed by `main`, an error without one was already reported by
+/// the command, e.g. as json.
+#[derive(Debug)]
+pub struct ExitCodeError {
+    pub code: i32,
+    pub report: Option<miette::Report>,
+}
+
+impl std::fmt::Display for ExitCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.report {
+            Some(report) => write!(f, "{}", report),
+            None => write!(f, "exited with code {}", self.code),
+        }
+    }
+}
+
+impl std::error::Error for ExitCodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.report.as_ref().and_then(|report| report.source())
+    }
+}
+
+impl miette::Diagnostic for ExitCodeError {
+    fn code<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.report.as_ref().and_then(|report| report.code())
+    }
+
+    fn severity(&self) -> Option<miette::Severity> {
+        self.report.as_ref().and_then(|report| report.severity())
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.report.as_ref().and_then(|report| report.help())
+    }
+
+    fn url<'a>(&'a self) -> Option<Box<dyn std::fmt::Display + 'a>> {
+        self.report.as_ref().and_then(|report| report.url())
+    }
+
+    fn source_code(&self) -> Option<&dyn miette::SourceCode> {
+        self.report.as_ref().and_then(|report| report.source_code())
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = miette::LabeledSpan> + '_>> {
+        self.report.as_ref().and_then(|report| report.labels())
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn Iterator<Item = &'a dyn miette::Diagnostic> + 'a>> {
+        self.report.as_ref().and_then(|report| report.related())
+    }
+
+    fn diagnostic_source(&self) -> Option<&dyn miette::Diagnostic> {
+        self.report
+            .as_ref()
+            .and_then(|report| report.diagnostic_source())
+    }
+}
+
 /// Whether to use colored log format.
 /// Option `Auto` enables color output only if the logging is done to a terminal
 /// and  `NO_COLOR` environment variable is not set.
@@ -0,0 +1,259 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::conda_explicit_spec::{
+    cwd, locked_platform_packages, relative_package_path, strip_direct_prefix,
+};
+use crate::cli::cli_config::PrefixUpdateConfig;
+use crate::Project;
+use clap::Parser;
+use miette::IntoDiagnostic;
+use pixi_manifest::FeaturesExt;
+use rattler_conda_types::Platform;
+use rattler_lock::{Package, PypiPackageData, UrlOrPath};
+use serde::Serialize;
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    /// Explicit path to export the environment file to. Prints to stdout if
+    /// not specified.
+    pub output_path: Option<PathBuf>,
+
+    /// The platform to render. Defaults to the only platform of the
+    /// environment in the lock file, or the current platform.
+    #[arg(long)]
+    pub platform: Option<Platform>,
+
+    /// The environment to render. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    /// The name to write into the environment file. Defaults to the name of
+    /// the environment.
+    #[arg(long, value_parser = super::parse_export_name)]
+    pub name: Option<String>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// A dependency entry of a conda `environment.yml`.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(untagged)]
+enum CondaEnvDependency {
+    Conda(String),
+    Pip { pip: Vec<String> },
+}
+
+/// The contents of a conda `environment.yml`.
+#[derive(Debug, Serialize)]
+struct CondaEnvironmentFile {
+    name: String,
+    channels: Vec<String>,
+    dependencies: Vec<CondaEnvDependency>,
+}
+
+/// Returns the pip requirement of a pypi package. Packages from an index are
+/// pinned by version, direct url and git packages keep their url and local
+/// packages are written relative to `base`, the directory conda runs pip in.
+fn pypi_requirement(package: &PypiPackageData, project_root: &Path, base: &Path) -> String {
+    match &package.url_or_path {
+        UrlOrPath::Path(path) => {
+            let path = relative_package_path(path, project_root, base);
+            if package.editable {
+                format!("-e {}", path.display())
+            } else {
+                path.display().to_string()
+            }
+        }
+        UrlOrPath::Url(url) if matches!(url.scheme(), "http" | "https") => {
+            format!("{}=={}", package.name, package.version)
+        }
+        UrlOrPath::Url(url) => strip_direct_prefix(package, url.as_str()).to_string(),
+    }
+}
+
+/// Converts the locked packages into a conda environment file with all the
+/// conda packages pinned to their exact version and build. Local pypi
+/// packages are written relative to `base`.
+fn build_environment_file(
+    name: String,
+    channels: Vec<String>,
+    locked_deps: impl IntoIterator<Item = Package>,
+    project_root: &Path,
+    base: &Path,
+) -> CondaEnvironmentFile {
+    let mut dependencies = Vec::new();
+    let mut pip_dependencies = Vec::new();
+
+    for package in locked_deps {
+        match package {
+            Package::Conda(p) => {
+                let record = p.package_record();
+                dependencies.push(CondaEnvDependency::Conda(format!(
+                    "{}={}={}",
+                    record.name.as_normalized(),
+                    record.version,
+                    record.build
+                )));
+            }
+            Package::Pypi(p) => {
+                pip_dependencies.push(pypi_requirement(p.data().package, project_root, base));
+            }
+        }
+    }
+
+    if !pip_dependencies.is_empty() {
+        dependencies.push(CondaEnvDependency::Pip {
+            pip: pip_dependencies,
+        });
+    }
+
+    CondaEnvironmentFile {
+        name,
+        channels,
+        dependencies,
+    }
+}
+
+pub async fn execute(project: Project, args: Args) -> miette::Result<()> {
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+    let lock_file = super::load_lock_file(
+        &project,
+        args.prefix_update_config.lock_file_usage(),
+        args.prefix_update_config.no_install(),
+        0,
+    )
+    .await?;
+
+    let platform = args
+        .platform
+        .unwrap_or_else(|| super::default_platform(&lock_file, &environment));
+    let locked_deps = locked_platform_packages(&lock_file, &environment, platform)?;
+
+    let channels = environment
+        .channels()
+        .into_iter()
+        .map(|c| c.to_string())
+        .collect();
+    let name = args
+        .name
+        .unwrap_or_else(|| environment.name().as_str().to_string());
+    // conda runs pip in the directory of the environment file
+    let base = match &args.output_path {
+        Some(output_path) => cwd()?.join(output_path.parent().unwrap_or(Path::new(""))),
+        None => cwd()?,
+    };
+    let env_file = build_environment_file(
+        name,
+        channels,
+        locked_deps,
+        environment.project().root(),
+        &base,
+    );
+    let contents = serde_yaml::to_string(&env_file).into_diagnostic()?;
+
+    match args.output_path {
+        Some(output_path) => {
+            tracing::info!("Creating conda environment file");
+            fs::write(&output_path, contents).map_err(|e| {
+                miette::miette!(
+                    "Could not write environment file '{}': {}",
+                    output_path.display(),
+                    e
+                )
+            })?;
+        }
+        None => print!("{}", contents),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::project::export::test_utils::pypi_package_with_url;
+    use rattler_lock::LockFile;
+
+    #[test]
+    fn test_build_environment_file() {
+        let project_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/cli/project/export/test-data/testenv");
+        let lock_file = LockFile::from_path(&project_dir.join("pixi.lock")).unwrap();
+        let packages = lock_file
+            .environment("with-pypi")
+            .unwrap()
+            .packages(Platform::Linux64)
+            .unwrap();
+
+        let env_file = build_environment_file(
+            "with-pypi".to_string(),
+            vec!["conda-forge".to_string()],
+            packages,
+            &project_dir,
+            &project_dir,
+        );
+
+        let contents = serde_yaml::to_string(&env_file).unwrap();
+        assert_eq!(
+            contents,
+            "name: with-pypi\n\
+             channels:\n\
+             - conda-forge\n\
+             dependencies:\n\
+             - _libgcc_mutex=0.1=conda_forge\n\
+             - bzip2=1.0.8=h4bc722e_7\n\
+             - libzlib=1.3.1=h4ab18f5_1\n\
+             - tzdata=2024a=h0c530f3_0\n\
+             - pip:\n  \
+             - six==1.16.0\n"
+        );
+    }
+
+    #[test]
+    fn test_pypi_requirement() {
+        let root = Path::new("/project");
+        let requirement = |url_or_path: UrlOrPath, editable: bool, base: &str| {
+            let package = pypi_package_with_url(url_or_path, None, editable);
+            pypi_requirement(&package, root, Path::new(base))
+        };
+
+        // Direct url and git packages keep their source
+        assert_eq!(
+            requirement(
+                UrlOrPath::Url(
+                    "direct+https://example.com/six-1.16.0-py2.py3-none-any.whl"
+                        .parse()
+                        .unwrap()
+                ),
+                false,
+                "/project"
+            ),
+            "https://example.com/six-1.16.0-py2.py3-none-any.whl"
+        );
+        assert_eq!(
+            requirement(
+                UrlOrPath::Url("git+https://github.com/benjaminp/six.git".parse().unwrap()),
+                false,
+                "/project"
+            ),
+            "git+https://github.com/benjaminp/six.git"
+        );
+
+        // Local packages are relative to the directory of the environment file
+        assert_eq!(
+            requirement(UrlOrPath::Path(PathBuf::from("pkgs/six")), true, "/out"),
+            "-e ../project/pkgs/six"
+        );
+        assert_eq!(
+            requirement(
+                UrlOrPath::Path(PathBuf::from("pkgs/six")),
+                false,
+                "/project"
+            ),
+            "pkgs/six"
+        );
+    }
+}
@@ -0,0 +1,6816 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::cli::cli_config::PrefixUpdateConfig;
+use crate::cli::{ExitCodeError, LockFileUsageArgs};
+use crate::environment::LockFileUsage;
+use crate::project::{Environment, HasProjectRef};
+use crate::Project;
+use chrono::{DateTime, SecondsFormat, Utc};
+use clap::Parser;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::stream::FuturesUnordered;
+use futures::StreamExt;
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use miette::IntoDiagnostic;
+use pep440_rs::{Operator, VersionSpecifiers};
+use pixi_consts::consts;
+use pixi_manifest::FeaturesExt;
+use pixi_progress::{default_progress_style, global_multi_progress};
+use rattler_conda_types::{
+    package::ArchiveIdentifier, ExplicitEnvironmentEntry, ExplicitEnvironmentSpec,
+    GenericVirtualPackage, MatchSpec, PackageName, PackageRecord, ParseStrictness, Platform,
+};
+use rattler_digest::{compute_file_digest, Sha256};
+use rattler_lock::{CondaPackage, LockFile, Package, PackageHashes, PypiPackageData, UrlOrPath};
+use rattler_solve::ChannelPriority;
+use reqwest_middleware::ClientWithMiddleware;
+use serde::Serialize;
+use url::Url;
+
+/// The hash algorithm to use in exported pypi requirements
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PypiHashAlgorithm {
+    Sha256,
+    Md5,
+    None,
+}
+
+/// What to do with the PyPI packages of an environment
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PypiMode {
+    /// Fail if the environment has any PyPI packages
+    #[default]
+    Bail,
+    /// Skip the PyPI packages and only export the conda packages
+    Ignore,
+    /// Also write the PyPI packages to a pip requirements file
+    Requirements,
+}
+
+impl PypiMode {
+    /// Returns the mode given by `--pypi-mode`, or the mode of the deprecated
+    /// `--write-pypi-requirements` and `--ignore-pypi-errors` flags it
+    /// supersedes. Warns if one of the deprecated flags is used.
+    pub fn from_args(
+        pypi_mode: Option<PypiMode>,
+        write_pypi_requirements: bool,
+        ignore_pypi_errors: bool,
+    ) -> Self {
+        if let Some(pypi_mode) = pypi_mode {
+            return pypi_mode;
+        }
+        let (flag, pypi_mode) = if write_pypi_requirements {
+            ("--write-pypi-requirements", PypiMode::Requirements)
+        } else if ignore_pypi_errors {
+            ("--ignore-pypi-errors", PypiMode::Ignore)
+        } else {
+            return PypiMode::Bail;
+        };
+        eprintln!(
+            "{}The '{}' option is deprecated and will be removed in the future.\nUse '{}' instead.",
+            console::style(console::Emoji("⚠️ ", "")).yellow(),
+            console::style(flag).bold().red(),
+            console::style(format!("--pypi-mode {}", pypi_mode.name()))
+                .bold()
+                .green(),
+        );
+        pypi_mode
+    }
+
+    /// The name of the mode on the command line.
+    fn name(self) -> &'static str {
+        match self {
+            PypiMode::Bail => "bail",
+            PypiMode::Ignore => "ignore",
+            PypiMode::Requirements => "requirements",
+        }
+    }
+}
+
+/// The line endings of the exported files
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Newline {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl Newline {
+    /// Converts all line endings of the contents to this line ending.
+    fn normalize(self, contents: &str) -> String {
+        let contents = contents.replace("\r\n", "\n");
+        match self {
+            Newline::Lf => contents,
+            Newline::Crlf => contents.replace('\n', "\r\n"),
+        }
+    }
+}
+
+/// The key to sort the exported packages by
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// The name of the package
+    Name,
+    /// The url of the package
+    #[default]
+    Url,
+    /// The size of the package archive, smallest first and packages of
+    /// unknown size last
+    Size,
+}
+
+/// The format of the exported conda packages
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A conda explicit spec file
+    Explicit,
+    /// A json document with the name, version, build, url and hashes of
+    /// every package
+    Json,
+}
+
+/// How errors of the export are reported
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// A human readable diagnostic
+    #[default]
+    Human,
+    /// A json object with the `code`, `message`, `context` and `help` of the
+    /// error, written to stderr
+    Json,
+}
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+#[clap(group(clap::ArgGroup::new("platforms").args(["all_platforms", "platform_from_lockfile"])))]
+#[clap(group(clap::ArgGroup::new("pypi_requirements").args(["write_pypi_requirements", "pypi_mode"])))]
+pub struct Args {
+    /// The platform to render. Defaults to the only platform of the
+    /// environment in the lock file, or the current platform. Common aliases
+    /// like `macos-arm64` or `linux-x86_64` are accepted as well.
+    #[arg(long, value_parser = validate_platform)]
+    pub platform: Option<String>,
+
+    /// Render the explicit spec for every platform of the environment.
+    #[arg(long, conflicts_with_all = ["platform", "stdout"])]
+    pub all_platforms: bool,
+
+    /// Render the explicit spec for every platform of the environment that is
+    /// present in the lock file, which may be a subset of the platforms in
+    /// the manifest.
+    #[arg(long, conflicts_with_all = ["platform", "stdout"])]
+    pub platform_from_lockfile: bool,
+
+    /// Write all platforms to a single file with an `@EXPLICIT` block per
+    /// platform. Noarch packages that are shared by all platforms are written
+    /// once to a leading `noarch` block. Requires `--all-platforms` or
+    /// `--platform-from-lockfile`.
+    #[arg(long)]
+    pub merged: bool,
+
+    /// Write the noarch packages to a separate `conda-noarch-{environment}`
+    /// explicit spec, the spec of the platform only contains the packages of
+    /// the platform subdir. With `--all-platforms` the noarch spec is written
+    /// once if it is the same for all platforms.
+    #[arg(long, conflicts_with_all = ["merged", "stdout"])]
+    pub split_noarch: bool,
+
+    /// Append a `# virtual-package: <name> <version> (<requirements>)` comment
+    /// for every virtual package the exported packages depend on, with the
+    /// version the environment was solved for. Conda ignores the comments.
+    #[arg(long, conflicts_with = "merged")]
+    pub include_virtual_packages: bool,
+
+    /// Warn about locked packages that a re-solve with the strict channel
+    /// priority of the environment might take from another channel, since the
+    /// explicit spec is installed without solving.
+    #[arg(long)]
+    pub check_channel_priority: bool,
+
+    /// Fail instead of warning if a package is downloaded over plain `http`.
+    #[arg(long)]
+    pub deny_insecure: bool,
+
+    /// Only write md5 hash fragments to the explicit spec, for conda clients
+    /// that don't accept sha256 fragments. Fails if a package has no md5
+    /// hash instead of falling back to its sha256 hash.
+    #[arg(long)]
+    pub md5_only: bool,
+
+    /// Remove the query of the package urls, e.g. auth tokens of a mirror
+    /// that must not be committed. The spec may not be installable without
+    /// separate credentials then.
+    #[arg(long)]
+    pub strip_query: bool,
+
+    /// Keep packages that are listed more than once with the same url,
+    /// instead of only keeping their first entry.
+    #[arg(long)]
+    pub no_dedup: bool,
+
+    /// Fail instead of warning if the explicit spec of a platform contains
+    /// packages of another platform subdir.
+    #[arg(long)]
+    pub strict_platform: bool,
+
+    /// Fail if the url of an exported package doesn't start with one of these
+    /// prefixes, e.g. `https://conda.anaconda.org/conda-forge/`. Can be
+    /// specified multiple times. The urls are checked after the channel
+    /// overrides are applied.
+    #[arg(long, value_name = "URL_PREFIX")]
+    pub allowed_channel: Vec<Url>,
+
+    /// Rewrite the urls of the exported packages that start with `FROM` to
+    /// start with `TO` instead, e.g.
+    /// `https://conda.anaconda.org/=https://mirror.internal/conda/`. Can be
+    /// specified multiple times, the first matching override is used.
+    #[arg(long, value_parser = parse_channel_override, value_name = "FROM=TO")]
+    pub channel_override: Vec<ChannelOverride>,
+
+    /// Don't rewrite the urls of the exported packages with the `mirrors` of
+    /// the pixi configuration, so the packages are installed from the
+    /// default urls of their channels.
+    #[arg(long)]
+    pub no_channel_alias: bool,
+
+    /// The environment to render. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    /// Only include the conda packages with these names in the explicit spec.
+    /// Accepts glob patterns like `numpy*` that are matched against the
+    /// normalized package names. Can be specified multiple times.
+    #[arg(long = "package", value_name = "NAME", value_parser = parse_package_pattern)]
+    pub packages: Vec<glob::Pattern>,
+
+    /// Only include this package and the locked packages it depends on,
+    /// directly or transitively. Can be specified multiple times.
+    #[arg(long, value_name = "PKG", conflicts_with = "packages")]
+    pub only_dependencies_of: Vec<PackageName>,
+
+    /// The name to use instead of the environment name in the exported file
+    /// names and in the json export.
+    #[arg(long, value_parser = super::parse_export_name, conflicts_with = "all_environments")]
+    pub name: Option<String>,
+
+    /// Render the explicit specs for every environment of the project.
+    #[arg(long, conflicts_with_all = ["environment", "stdout"])]
+    pub all_environments: bool,
+
+    /// Only export the conda packages that are not locked for this baseline
+    /// environment, compared by their url.
+    #[arg(long, value_name = "ENVIRONMENT", conflicts_with = "all_environments")]
+    pub diff_against: Option<String>,
+
+    /// The directory to write the exported files to. Defaults to the current
+    /// working directory.
+    #[arg(long, conflicts_with = "stdout")]
+    pub output_dir: Option<PathBuf>,
+
+    /// The path to write the conda file to, instead of a file named by
+    /// `--filename-template` in the output directory. Only a single platform
+    /// of a single environment can be exported to it.
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["stdout", "all_environments", "platforms", "split_noarch", "only_pypi"]
+    )]
+    pub conda_output: Option<PathBuf>,
+
+    /// The path to write the pypi requirements file to, instead of a file
+    /// named by `--filename-template` in the output directory. Only a single
+    /// platform of a single environment can be exported to it.
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "pypi_requirements",
+        conflicts_with_all = ["all_environments", "platforms"]
+    )]
+    pub pypi_output: Option<PathBuf>,
+
+    /// The template for the names of the exported files. Supports the
+    /// `{platform}`, `{environment}` and `{extension}` placeholders, e.g.
+    /// `{environment}.{platform}.{extension}`.
+    #[arg(long, value_parser = parse_filename_template, conflicts_with = "stdout")]
+    pub filename_template: Option<String>,
+
+    /// The extension of the exported explicit spec files, e.g. `txt` to
+    /// follow the convention of conda.
+    #[arg(long, default_value = "lock", value_parser = parse_extension)]
+    pub extension: String,
+
+    /// The format to export the conda packages in.
+    #[arg(long, default_value = "explicit", value_enum)]
+    pub format: ExportFormat,
+
+    /// The format to report a failed export in.
+    #[arg(long, default_value = "human", value_enum)]
+    pub error_format: ErrorFormat,
+
+    /// Include the build string of every package in the json export. Has no
+    /// effect on the explicit format, where the build is part of the url.
+    #[arg(long, default_value_t = true, action = clap::ArgAction::Set, value_name = "BOOL")]
+    pub include_build_string: bool,
+
+    /// Gzip compress the exported files and add a `.gz` suffix to their names.
+    #[arg(long, conflicts_with = "stdout")]
+    pub compress: bool,
+
+    /// Print the sha256 checksum of every exported file in the format of
+    /// `sha256sum`, so it can be verified with `sha256sum -c`.
+    #[arg(long, conflicts_with = "stdout")]
+    pub print_checksum: bool,
+
+    /// Log the total download size of the conda packages of every exported
+    /// file at the end of the export, with a breakdown per channel. The sizes
+    /// are read from the lock file, packages without a recorded size are
+    /// counted separately.
+    #[arg(long)]
+    pub print_sizes: bool,
+
+    /// Write a json report listing the path, platform, environment and
+    /// number of packages of every exported file to this path.
+    #[arg(long, value_name = "PATH", conflicts_with = "stdout")]
+    pub report: Option<PathBuf>,
+
+    /// Run this shell command for every exported file after all files are
+    /// written, with `PIXI_EXPORT_FILE`, `PIXI_EXPORT_PLATFORM` and
+    /// `PIXI_EXPORT_ENVIRONMENT` set. The export fails if the command fails.
+    #[arg(long, value_name = "CMD", conflicts_with_all = ["stdout", "dry_run"])]
+    pub post_export_command: Option<String>,
+
+    /// Fail if any of the exported files already exists instead of
+    /// overwriting it. All files are checked before any of them is written.
+    #[arg(long, conflicts_with = "stdout")]
+    pub no_clobber: bool,
+
+    /// Merge the packages of an existing explicit spec at the target into the
+    /// exported one instead of overwriting it, e.g. to compose a spec from
+    /// several environments. The packages are deduplicated by url and sorted,
+    /// the header is the one of the new export.
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "stdout",
+            "no_clobber",
+            "compress",
+            "merged",
+            "split_noarch",
+            "annotate",
+            "annotate_packages",
+            "group_by_channel",
+        ]
+    )]
+    pub append: bool,
+
+    /// Build the exports without writing any files, instead print the paths
+    /// that would be written and the number of packages each would contain.
+    #[arg(long, conflicts_with_all = ["stdout", "print_checksum", "report"])]
+    pub dry_run: bool,
+
+    /// Write the explicit spec to stdout instead of a file.
+    #[arg(long, conflicts_with_all = ["write_pypi_requirements", "write_pypi_constraints"])]
+    pub stdout: bool,
+
+    /// Indent every line printed to stdout by this many spaces, e.g. to embed
+    /// the export in a YAML block scalar.
+    #[arg(long, value_name = "N", requires = "stdout")]
+    pub indent: Option<usize>,
+
+    /// The line endings of the exported conda and pypi files.
+    #[arg(long, default_value = "lf", value_enum)]
+    pub newline: Newline,
+
+    /// Abort the export of `--all-platforms` on the first platform that fails
+    /// instead of skipping it and exporting the other platforms.
+    #[arg(long)]
+    pub fail_fast: bool,
+
+    /// Log the progress messages of the export at debug instead of info level
+    /// and don't show progress bars. Unlike the global `--quiet`, which this
+    /// can't be named after since it is defined for every command, the
+    /// warnings and the other logs of pixi are kept.
+    #[arg(long)]
+    pub silent: bool,
+
+    /// Add `# channel: <url>` comments with the channels of the environment,
+    /// in order of priority, above the explicit specs and a `# subdir:
+    /// <subdir>` comment above every package.
+    #[arg(long)]
+    pub annotate: bool,
+
+    /// Add a `# {name} {version} license={license} size={size}` comment above
+    /// every package of the explicit specs. Conda ignores the comments.
+    #[arg(long)]
+    pub annotate_packages: bool,
+
+    /// List the packages of the explicit specs grouped by their channel, each
+    /// group preceded by a `# --- channel: <url> ---` comment. Conda ignores
+    /// the comments.
+    #[arg(long)]
+    pub group_by_channel: bool,
+
+    /// Don't include the generated-by header comment in the exported files.
+    #[arg(long)]
+    pub no_header: bool,
+
+    /// Record the time of the export as a `# generated-at: <RFC3339>` comment
+    /// in the header. Off by default since it makes the files differ between
+    /// exports. Ignored with `--no-header`.
+    #[arg(long)]
+    pub timestamp: bool,
+
+    /// Keep the packages in lock-file order instead of sorting them.
+    #[arg(long, conflicts_with = "sort_by")]
+    pub no_sort: bool,
+
+    /// The key to sort the packages by, ties are broken by the url. Conda
+    /// doesn't depend on the order, but a stable order keeps the diffs of the
+    /// exported files readable.
+    #[arg(long, value_enum, default_value_t = SortKey::Url)]
+    pub sort_by: SortKey,
+
+    /// Don't move the noarch packages after the packages of the platform
+    /// subdir, which is the order conda installs them in.
+    #[arg(long)]
+    pub no_reorder: bool,
+
+    /// Keep the PyPI packages of the requirements and constraints files in
+    /// the lock-file order instead of sorting them. `--no-sort` only keeps
+    /// the order of the conda packages.
+    #[arg(long)]
+    pub no_sort_pypi: bool,
+
+    /// What to do with the PyPI packages of the environment: fail the export
+    /// (`bail`, the default), skip them (`ignore`) or also write them to a pip
+    /// requirements file (`requirements`). The options of the requirements
+    /// file need `requirements`.
+    #[arg(long, value_enum, conflicts_with_all = ["write_pypi_requirements", "ignore_pypi_errors"])]
+    pub pypi_mode: Option<PypiMode>,
+
+    /// Also write a pip requirements file with the PyPI dependencies.
+    /// Deprecated, use `--pypi-mode requirements` instead.
+    #[arg(long, default_value = "false", conflicts_with = "ignore_pypi_errors")]
+    pub write_pypi_requirements: bool,
+
+    /// Also write a pip constraints file with `name==version` pins of the
+    /// PyPI dependencies. Local and editable packages are omitted.
+    #[arg(long, conflicts_with = "ignore_pypi_errors")]
+    pub write_pypi_constraints: bool,
+
+    /// Append the pip requirements of the PyPI dependencies as `# pypi: `
+    /// comments to the explicit spec instead of writing a separate
+    /// requirements file. Conda ignores the comments, but the file documents
+    /// the whole environment.
+    #[arg(long, conflicts_with_all = ["write_pypi_requirements", "ignore_pypi_errors", "merged"])]
+    pub pypi_inline: bool,
+
+    /// Only write the conda packages, without any pypi files. This is the
+    /// default unless pypi files are requested.
+    #[arg(long, conflicts_with_all = ["only_pypi", "write_pypi_requirements", "write_pypi_constraints", "pypi_inline"])]
+    pub only_conda: bool,
+
+    /// Only write the pip requirements file with the PyPI dependencies and
+    /// skip the conda packages. Implies `--pypi-mode requirements`.
+    #[arg(long, conflicts_with_all = ["ignore_pypi_errors", "stdout"])]
+    pub only_pypi: bool,
+
+    /// The hash algorithm to use in the pypi requirements file. Falls back to
+    /// the available hash if a package doesn't have the requested one.
+    #[arg(long, default_value = "sha256", value_enum)]
+    pub pypi_hash_algo: PypiHashAlgorithm,
+
+    /// Fail if any PyPI package in the requirements file has no hash. Local
+    /// path packages are exempt since pip can't verify their hashes.
+    #[arg(long, requires = "pypi_requirements")]
+    pub require_pypi_hashes: bool,
+
+    /// The index url to declare with `--index-url` at the top of the pypi
+    /// requirements file.
+    #[arg(long, value_name = "URL", requires = "pypi_requirements")]
+    pub pypi_index_url: Option<Url>,
+
+    /// An extra index url to declare with `--extra-index-url` at the top of
+    /// the pypi requirements file. Can be specified multiple times.
+    #[arg(long, value_name = "URL", requires = "pypi_requirements")]
+    pub pypi_extra_index_url: Vec<Url>,
+
+    /// A pip option to write as its own line at the top of the pypi
+    /// requirements file, e.g. `--pip-option=--prefer-binary`. Can be
+    /// specified multiple times.
+    #[arg(
+        long,
+        value_name = "OPTION",
+        allow_hyphen_values = true,
+        value_parser = parse_pip_option,
+        requires = "pypi_requirements"
+    )]
+    pub pip_option: Vec<String>,
+
+    /// Write local PyPI packages to the requirements file with a path relative
+    /// to the requirements file instead of the path from the lock file.
+    #[arg(long, requires = "pypi_requirements")]
+    pub relative_paths: bool,
+
+    /// Also write the build requirements of the PyPI source distributions to
+    /// the requirements file, for an offline install. The lock file doesn't
+    /// record them yet, so the source distributions are only listed in a
+    /// warning.
+    #[arg(long, requires = "pypi_requirements")]
+    pub include_build_deps: bool,
+
+    /// PyPI dependencies are not supported in the conda explicit spec file.
+    /// This flag allows creating the spec file even if PyPI dependencies are
+    /// present. Deprecated, use `--pypi-mode ignore` instead.
+    #[arg(long, default_value = "false")]
+    pub ignore_pypi_errors: bool,
+
+    /// Retry updating the lock file this many times, with an exponential
+    /// backoff, when it fails because of a transient network error.
+    #[arg(long, default_value = "0", value_name = "N")]
+    pub max_retries: u32,
+
+    /// Check that the url of every exported conda package is reachable with
+    /// a HEAD request before the files are written.
+    #[arg(long, conflicts_with = "stdout")]
+    pub verify_urls: bool,
+
+    /// The timeout in seconds of every request of `--verify-urls`.
+    #[arg(
+        long,
+        default_value = "30",
+        value_name = "SECONDS",
+        requires = "verify_urls"
+    )]
+    pub verify_timeout: u64,
+
+    /// Export from this lock file instead of the lock file of the project,
+    /// e.g. a pinned snapshot. The file is used as-is, it is never updated.
+    #[arg(long, value_name = "PATH", conflicts_with = "locked")]
+    pub lockfile: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub lock_file_usage: LockFileUsageArgs,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// Validates that a filename template contains the placeholders needed to
+/// give every exported platform and environment a unique file name.
+fn parse_filename_template(s: &str) -> Result<String, String> {
+    for placeholder in ["{platform}", "{environment}"] {
+        if !s.contains(placeholder) {
+            return Err(format!("the template must contain `{}`", placeholder));
+        }
+    }
+    Ok(s.to_string())
+}
+
+/// Returns the platform whose name is closest to `requested`, if any is a
+/// near miss.
+fn closest_platform(
+    requested: &str,
+    candidates: impl IntoIterator<Item = Platform>,
+) -> Option<Platform> {
+    candidates
+        .into_iter()
+        .map(|platform| (strsim::levenshtein(requested, platform.as_str()), platform))
+        .filter(|(distance, _)| *distance <= 3)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, platform)| platform)
+}
+
+/// Returns the platform that a commonly used alternative name refers to, e.g.
+/// `macos-arm64` for `osx-arm64`.
+fn resolve_platform_alias(s: &str) -> Option<Platform> {
+    let platform = match s.to_lowercase().as_str() {
+        "linux-x86_64" | "linux-amd64" | "linux-x64" => Platform::Linux64,
+        "linux-arm64" => Platform::LinuxAarch64,
+        "macos-64" | "macos-x86_64" | "osx-x86_64" | "darwin-x86_64" => Platform::Osx64,
+        "arm64" | "macos-arm64" | "macos-aarch64" | "osx-aarch64" | "darwin-arm64" => {
+            Platform::OsxArm64
+        }
+        "windows-64" | "win-x86_64" | "win-amd64" | "win-x64" => Platform::Win64,
+        "windows-arm64" | "win-aarch64" => Platform::WinArm64,
+        _ => return None,
+    };
+    Some(platform)
+}
+
+/// Validates a platform given on the command line, the aliases are resolved
+/// by [`parse_platform`] when the platform is used.
+fn validate_platform(s: &str) -> Result<String, String> {
+    parse_platform(s).map(|_| s.to_string())
+}
+
+/// Parses a platform or one of its aliases, suggesting the closest known
+/// platform on a typo.
+fn parse_platform(s: &str) -> Result<Platform, String> {
+    if let Ok(platform) = Platform::from_str(s) {
+        return Ok(platform);
+    }
+    if let Some(platform) = resolve_platform_alias(s) {
+        tracing::debug!("resolved the platform alias '{}' to '{}'", s, platform);
+        return Ok(platform);
+    }
+    Err(match closest_platform(s, Platform::all()) {
+        Some(platform) => format!(
+            "'{}' is not a known platform, did you mean '{}'?",
+            s, platform
+        ),
+        None => format!("'{}' is not a known platform", s),
+    })
+}
+
+/// Validates the extension of the exported explicit spec files.
+fn parse_extension(s: &str) -> Result<String, String> {
+    let extension = s.strip_prefix('.').unwrap_or(s);
+    if extension.is_empty() {
+        return Err("the extension can't be empty".to_string());
+    }
+    if extension.contains(['/', '\\']) {
+        return Err(format!(
+            "the extension can't contain path separators, got `{}`",
+            s
+        ));
+    }
+    Ok(extension.to_string())
+}
+
+/// Parses a `--package` filter, a package name or a glob pattern. Patterns
+/// are lowercased since they are matched against the normalized names.
+fn parse_package_pattern(s: &str) -> Result<glob::Pattern, String> {
+    glob::Pattern::new(&s.to_lowercase())
+        .map_err(|e| format!("invalid package pattern `{}`: {}", s, e))
+}
+
+/// Parses a `--pip-option`, which has to start with `-` so a package name is
+/// not mistaken for an option.
+fn parse_pip_option(s: &str) -> Result<String, String> {
+    if !s.starts_with('-') {
+        return Err(format!(
+            "pip options start with `-`, got `{}`. Add packages to the manifest instead",
+            s
+        ));
+    }
+    Ok(s.trim().to_string())
+}
+
+/// Rewrites the urls of packages that start with `from` to start with `to`,
+/// e.g. to install the packages from a mirror of the channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelOverride {
+    pub from: String,
+    pub to: String,
+}
+
+impl ChannelOverride {
+    /// Returns the url with the override applied, or `None` if the url
+    /// doesn't start with the prefix of the override.
+    fn apply(&self, url: &Url) -> Option<Result<Url, url::ParseError>> {
+        let rest = url.as_str().strip_prefix(self.from.as_str())?;
+        Some(Url::parse(&format!("{}{}", self.to, rest)))
+    }
+}
+
+/// Parses a channel override of the form `from=to`.
+fn parse_channel_override(s: &str) -> Result<ChannelOverride, String> {
+    let (from, to) = s
+        .split_once('=')
+        .ok_or_else(|| format!("invalid FROM=TO: no `=` found in `{}`", s))?;
+    if from.is_empty() {
+        return Err("the url prefix to override can't be empty".to_string());
+    }
+    Url::parse(to).map_err(|e| format!("invalid url `{}`: {}", to, e))?;
+    Ok(ChannelOverride {
+        from: from.to_string(),
+        to: to.to_string(),
+    })
+}
+
+/// Returns the channel overrides for the `mirrors` of the pixi configuration,
+/// using the first mirror of every channel. The most specific prefix comes
+/// first, so it takes precedence over a mirror of its parent. Mirrors that
+/// conda can't download from, like `oci://` registries, are skipped.
+fn mirror_channel_overrides(mirrors: &HashMap<Url, Vec<Url>>) -> Vec<ChannelOverride> {
+    let with_slash = |url: &Url| {
+        let url = url.as_str();
+        match url.ends_with('/') {
+            true => url.to_string(),
+            false => format!("{}/", url),
+        }
+    };
+
+    mirrors
+        .iter()
+        .filter_map(|(from, to)| {
+            let to = to.first()?;
+            if !matches!(to.scheme(), "http" | "https" | "file") {
+                tracing::debug!(
+                    "not rewriting the urls of '{}' to mirror '{}', its scheme is not supported by conda",
+                    from,
+                    to
+                );
+                return None;
+            }
+            Some(ChannelOverride {
+                from: with_slash(from),
+                to: with_slash(to),
+            })
+        })
+        .sorted_by(|a, b| b.from.len().cmp(&a.from.len()).then_with(|| a.from.cmp(&b.from)))
+        .collect()
+}
+
+/// Applies the first matching channel override to the url of a package.
+fn override_channel(url: &Url, channel_overrides: &[ChannelOverride]) -> miette::Result<Url> {
+    match channel_overrides.iter().find_map(|o| o.apply(url)) {
+        Some(overridden) => overridden
+            .map_err(|e| miette::miette!("could not override the channel of '{}': {}", url, e)),
+        None => Ok(url.clone()),
+    }
+}
+
+/// Returns the file name of an exported file. Without a template the file is
+/// named `<prefix>-<platform>-<environment>.<extension>`.
+fn output_file_name(
+    template: Option<&str>,
+    prefix: &str,
+    platform: &str,
+    environment_name: &str,
+    extension: &str,
+) -> String {
+    match template {
+        Some(template) => template
+            .replace("{platform}", platform)
+            .replace("{environment}", environment_name)
+            .replace("{extension}", extension),
+        None => format!("{}-{}-{}.{}", prefix, platform, environment_name, extension),
+    }
+}
+
+/// Options for [`Project::export_conda_explicit_spec`] and
+/// [`Project::locked_packages`].
+#[derive(Debug, Clone)]
+pub struct ExplicitSpecOptions {
+    /// Defines what to do if the lock-file is out of date
+    pub lock_file_usage: LockFileUsage,
+
+    /// Don't install the environment when the lock-file is updated.
+    pub no_install: bool,
+
+    /// Only include the conda packages whose normalized names match one of
+    /// these patterns, all packages are included if this is empty.
+    pub packages: Vec<glob::Pattern>,
+
+    /// Only include these packages and their transitive dependencies, all
+    /// packages are included if this is empty.
+    pub dependencies_of: Vec<PackageName>,
+
+    /// The order of the packages in the explicit spec.
+    pub order: PackageOrder,
+
+    /// Skip PyPI packages instead of returning an error.
+    pub ignore_pypi_packages: bool,
+
+    /// Rewrite the urls of the packages, the first matching override is used.
+    pub channel_overrides: Vec<ChannelOverride>,
+
+    /// Fail instead of warning for packages that are downloaded over plain
+    /// `http`.
+    pub deny_insecure: bool,
+
+    /// Require an md5 hash for every package instead of falling back to the
+    /// sha256 hash.
+    pub md5_only: bool,
+
+    /// Remove the query of the package urls.
+    pub strip_query: bool,
+
+    /// Keep packages with a duplicate url instead of only their first entry.
+    pub no_dedup: bool,
+
+    /// Fail instead of warning for packages of another platform subdir.
+    pub strict_platform: bool,
+
+    /// Show a progress bar while the packages are processed.
+    pub show_progress: bool,
+
+    /// The url prefixes that the packages must be downloaded from, any url is
+    /// allowed if this is empty.
+    pub allowed_channels: Vec<Url>,
+
+    /// Retry updating the lock-file this many times on transient network
+    /// errors.
+    pub max_retries: u32,
+}
+
+impl Default for ExplicitSpecOptions {
+    fn default() -> Self {
+        Self {
+            lock_file_usage: LockFileUsage::default(),
+            no_install: false,
+            packages: Vec::new(),
+            dependencies_of: Vec::new(),
+            order: PackageOrder::default(),
+            ignore_pypi_packages: false,
+            channel_overrides: Vec::new(),
+            deny_insecure: false,
+            md5_only: false,
+            strip_query: false,
+            no_dedup: false,
+            strict_platform: false,
+            show_progress: false,
+            allowed_channels: Vec::new(),
+            max_retries: 0,
+        }
+    }
+}
+
+impl Args {
+    /// Returns how the lock file should be used, `--frozen` and `--locked`
+    /// take precedence over `--no-lockfile-update`.
+    fn lock_file_usage(&self) -> LockFileUsage {
+        match LockFileUsage::from(self.lock_file_usage) {
+            LockFileUsage::Update => self.prefix_update_config.lock_file_usage(),
+            lock_file_usage => lock_file_usage,
+        }
+    }
+
+    /// Fills in the arguments that were not given on the command line from
+    /// the export config of the project.
+    fn with_export_config(mut self, config: super::ExportConfig) -> Self {
+        if self.environment.is_none() && !self.all_environments {
+            self.environment = config.environment;
+        }
+        if self.platform.is_none() && !self.all_platforms && !self.platform_from_lockfile {
+            if config.all_platforms && !self.stdout {
+                self.all_platforms = true;
+            } else {
+                self.platform = config.platform.map(|platform| platform.to_string());
+            }
+        }
+        if self.output_dir.is_none() && !self.stdout {
+            self.output_dir = config.output_dir;
+        }
+        self
+    }
+
+    /// Checks the arguments that depend on the exported platforms. These are
+    /// checked after the export config is applied since it can enable
+    /// `all-platforms`, which clap doesn't see.
+    fn check_platforms(&self) -> miette::Result<()> {
+        let all_platforms = self.all_platforms || self.platform_from_lockfile;
+        let (flags, message, help) = if all_platforms {
+            (
+                vec![
+                    ("--conda-output", self.conda_output.is_some()),
+                    ("--pypi-output", self.pypi_output.is_some()),
+                ],
+                "a single path can't hold the files of all platforms",
+                "pass `--platform` to export a single platform",
+            )
+        } else {
+            (
+                vec![("--merged", self.merged), ("--fail-fast", self.fail_fast)],
+                "these options need all platforms to be exported",
+                "pass `--all-platforms` or `--platform-from-lockfile`",
+            )
+        };
+        let flags = flags
+            .into_iter()
+            .filter_map(|(flag, present)| present.then_some(flag))
+            .collect_vec();
+        if !flags.is_empty() {
+            miette::bail!(
+                code = "conflicting-arguments",
+                help = help,
+                "{}: {}",
+                message,
+                flags.iter().map(|flag| format!("`{flag}`")).format(", ")
+            );
+        }
+        Ok(())
+    }
+
+    /// Folds `--pypi-mode` into the flags it supersedes. The combinations
+    /// that depend on the value of the mode can't be rejected by clap, so
+    /// they are checked here.
+    fn apply_pypi_mode(&mut self) -> miette::Result<()> {
+        let pypi_mode = match PypiMode::from_args(
+            self.pypi_mode,
+            self.write_pypi_requirements,
+            self.ignore_pypi_errors,
+        ) {
+            // `--only-pypi` implies the requirements file
+            PypiMode::Bail if self.only_pypi && self.pypi_mode.is_none() => PypiMode::Requirements,
+            pypi_mode => pypi_mode,
+        };
+
+        let mut conflicting = match pypi_mode {
+            // The stdout export returns before the pypi files are written
+            PypiMode::Requirements => vec![
+                ("--pypi-inline", self.pypi_inline),
+                ("--only-conda", self.only_conda),
+                ("--stdout", self.stdout),
+            ],
+            PypiMode::Bail | PypiMode::Ignore => vec![
+                ("--only-pypi", self.only_pypi),
+                ("--pypi-output", self.pypi_output.is_some()),
+                ("--require-pypi-hashes", self.require_pypi_hashes),
+                ("--pypi-index-url", self.pypi_index_url.is_some()),
+                (
+                    "--pypi-extra-index-url",
+                    !self.pypi_extra_index_url.is_empty(),
+                ),
+                ("--pip-option", !self.pip_option.is_empty()),
+                ("--relative-paths", self.relative_paths),
+                ("--include-build-deps", self.include_build_deps),
+            ],
+        };
+        if pypi_mode == PypiMode::Ignore {
+            conflicting.extend([
+                ("--write-pypi-constraints", self.write_pypi_constraints),
+                ("--pypi-inline", self.pypi_inline),
+            ]);
+        }
+        let conflicting = conflicting
+            .into_iter()
+            .filter_map(|(flag, present)| present.then_some(flag))
+            .collect_vec();
+        if !conflicting.is_empty() {
+            let help = match pypi_mode {
+                PypiMode::Requirements => "`--pypi-mode requirements` writes the PyPI packages to a separate requirements file",
+                PypiMode::Bail | PypiMode::Ignore => "the pypi requirements options need `--pypi-mode requirements`",
+            };
+            miette::bail!(
+                code = "conflicting-arguments",
+                help = help,
+                "`--pypi-mode {}` cannot be combined with {}",
+                pypi_mode.name(),
+                conflicting
+                    .iter()
+                    .map(|flag| format!("`{flag}`"))
+                    .format(", ")
+            );
+        }
+
+        self.pypi_mode = Some(pypi_mode);
+        self.write_pypi_requirements = pypi_mode == PypiMode::Requirements;
+        self.ignore_pypi_errors = pypi_mode == PypiMode::Ignore;
+        Ok(())
+    }
+
+    /// Returns the platform to render, with its alias resolved.
+    fn platform(&self) -> miette::Result<Option<Platform>> {
+        self.platform
+            .as_deref()
+            .map(parse_platform)
+            .transpose()
+            .map_err(|e| miette::miette!("{}", e))
+    }
+
+    /// Returns the name to use for the environment in the exported files.
+    fn export_name<'a>(&'a self, environment_name: &'a str) -> &'a str {
+        self.name.as_deref().unwrap_or(environment_name)
+    }
+
+    /// Returns the order of the packages in the exported files.
+    fn package_order(&self) -> PackageOrder {
+        PackageOrder {
+            sort_by: (!self.no_sort).then_some(self.sort_by),
+            noarch_last: !self.no_reorder,
+        }
+    }
+
+    /// Returns the options to build the explicit specs with.
+    fn spec_options(&self) -> ExplicitSpecOptions {
+        ExplicitSpecOptions {
+            lock_file_usage: self.lock_file_usage(),
+            no_install: self.prefix_update_config.no_install(),
+            packages: self.packages.clone(),
+            dependencies_of: self.only_dependencies_of.clone(),
+            order: self.package_order(),
+            ignore_pypi_packages: self.ignore_pypi_errors,
+            channel_overrides: self.channel_override.clone(),
+            deny_insecure: self.deny_insecure,
+            md5_only: self.md5_only,
+            strip_query: self.strip_query,
+            no_dedup: self.no_dedup,
+            strict_platform: self.strict_platform,
+            show_progress: self.show_progress(),
+            allowed_channels: self.allowed_channel.clone(),
+            max_retries: self.max_retries,
+        }
+    }
+
+    /// Returns true if progress bars should be shown, which requires stderr to
+    /// be a terminal and neither `--silent` nor the global `--quiet` or
+    /// `--no-progress` to be set.
+    fn show_progress(&self) -> bool {
+        !self.silent
+            && tracing::enabled!(tracing::Level::INFO)
+            && !global_multi_progress().is_hidden()
+            && std::io::stderr().is_terminal()
+    }
+}
+
+/// Indents every non-empty line of the contents by `indent` spaces.
+fn indent_lines(contents: &str, indent: usize) -> String {
+    let prefix = " ".repeat(indent);
+    contents
+        .split_inclusive('\n')
+        .map(|line| match line {
+            "\n" => line.to_string(),
+            line => format!("{}{}", prefix, line),
+        })
+        .collect()
+}
+
+/// Prints an export to stdout, indented if `--indent` is set.
+fn print_export(args: &Args, contents: &str) {
+    match args.indent {
+        Some(indent) => print!("{}", indent_lines(contents, indent)),
+        None => print!("{}", contents),
+    }
+}
+
+/// Returns a progress bar with `len` steps, or a hidden one if `show` is not
+/// set.
+fn export_progress_bar(show: bool, prefix: &'static str, len: usize) -> ProgressBar {
+    if !show {
+        return ProgressBar::hidden();
+    }
+    let pb = global_multi_progress().add(ProgressBar::new(len as u64));
+    pb.set_style(default_progress_style());
+    pb.set_prefix(prefix);
+    pb
+}
+
+/// Logs a progress message of the export, at debug level if `--silent` is set.
+fn log_progress(args: &Args, message: std::fmt::Arguments<'_>) {
+    if args.silent {
+        tracing::debug!("{}", message);
+    } else {
+        tracing::info!("{}", message);
+    }
+}
+
+/// Returns the path of an exported file in the output directory. With
+/// `--compress` a `.gz` suffix is added, unless the name already has one.
+/// The `--conda-output` and `--pypi-output` paths of the conda and the
+/// requirements file are used as-is.
+fn export_path(
+    args: &Args,
+    output_dir: &Path,
+    prefix: &str,
+    platform: &str,
+    environment_name: &str,
+    extension: &str,
+) -> PathBuf {
+    let explicit_path = match prefix {
+        "conda" => args.conda_output.as_ref(),
+        "requirements" => args.pypi_output.as_ref(),
+        _ => None,
+    };
+    if let Some(path) = explicit_path {
+        return path.clone();
+    }
+
+    let mut file_name = output_file_name(
+        args.filename_template.as_deref(),
+        prefix,
+        platform,
+        args.export_name(environment_name),
+        extension,
+    );
+    if args.compress && !file_name.ends_with(".gz") {
+        file_name.push_str(".gz");
+    }
+    output_dir.join(file_name)
+}
+
+/// Writes an exported file, gzip compressing the contents if the file name
+/// ends with `.gz`.
+fn write_export_file(target: &Path, contents: &str) -> std::io::Result<()> {
+    if target.extension().is_some_and(|ext| ext == "gz") {
+        let mut encoder = GzEncoder::new(fs::File::create(target)?, Compression::default());
+        encoder.write_all(contents.as_bytes())?;
+        encoder.finish()?;
+        Ok(())
+    } else {
+        fs::write(target, contents)
+    }
+}
+
+/// Removes the files that are exported more than once with the same contents,
+/// like the noarch spec of `--split-noarch` for every platform. Errors if a
+/// file would be written with different contents.
+fn dedup_exported_files(files: Vec<ExportedFile>) -> miette::Result<Vec<ExportedFile>> {
+    let mut deduped: Vec<ExportedFile> = Vec::with_capacity(files.len());
+    for file in files {
+        match deduped.iter().find(|existing| existing.path == file.path) {
+            Some(existing) if existing.contents == file.contents => {}
+            Some(_) => miette::bail!(
+                code = "conflicting-export",
+                "'{}' would be written multiple times with different contents",
+                file.path.display()
+            ),
+            None => deduped.push(file),
+        }
+    }
+    Ok(deduped)
+}
+
+/// Logs a line with the number of packages of every exported file, followed
+/// by the totals if multiple files were exported.
+fn log_export_summary(args: &Args, files: &[ExportedFile]) {
+    let kind = |file: &ExportedFile| if file.pypi { "pypi" } else { "conda" };
+    for file in files {
+        log_progress(
+            args,
+            format_args!(
+                "Exported {} {} packages to {}",
+                file.packages,
+                kind(file),
+                file.path.display()
+            ),
+        );
+    }
+
+    if files.len() > 1 {
+        let (pypi, conda): (Vec<_>, Vec<_>) = files.iter().partition(|file| file.pypi);
+        log_progress(
+            args,
+            format_args!(
+                "Exported {} conda packages and {} pypi packages to {} files",
+                conda.iter().map(|file| file.packages).sum::<usize>(),
+                pypi.iter().map(|file| file.packages).sum::<usize>(),
+                files.len()
+            ),
+        );
+    }
+}
+
+/// The download size of the exported conda packages of a single channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct ChannelSize {
+    packages: usize,
+    /// The summed size of the packages that record their size.
+    size: u64,
+    /// The number of packages without a recorded size.
+    unknown: usize,
+}
+
+impl ChannelSize {
+    fn add(&mut self, size: Option<u64>) {
+        self.packages += 1;
+        match size {
+            Some(size) => self.size += size,
+            None => self.unknown += 1,
+        }
+    }
+}
+
+impl std::fmt::Display for ChannelSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} packages",
+            indicatif::HumanBytes(self.size),
+            self.packages
+        )?;
+        if self.unknown > 0 {
+            write!(f, ", {} without a recorded size", self.unknown)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Sums the download sizes of the conda packages per channel, which is the
+/// url of the package in the lock file without the subdir and the file name.
+fn channel_sizes<'a>(
+    packages: impl IntoIterator<Item = &'a CondaPackage>,
+) -> BTreeMap<String, ChannelSize> {
+    let mut sizes: BTreeMap<String, ChannelSize> = BTreeMap::new();
+    for package in packages {
+        sizes
+            .entry(channel_of_entry(package.url().as_str()).to_string())
+            .or_default()
+            .add(package.package_record().size);
+    }
+    sizes
+}
+
+/// Renders the `--print-sizes` summary of an exported file: the total
+/// download size of its conda packages followed by a line per channel.
+fn size_summary(file: &ExportedFile) -> String {
+    let mut total = ChannelSize::default();
+    for size in file.sizes.values() {
+        total.packages += size.packages;
+        total.size += size.size;
+        total.unknown += size.unknown;
+    }
+    let mut summary = format!("Download size of {}: {}", file.path.display(), total);
+    for (channel, size) in &file.sizes {
+        summary.push_str(&format!("\n  {}: {}", channel, size));
+    }
+    summary
+}
+
+/// Logs the `--print-sizes` summary of every exported conda file.
+fn log_size_summary(files: &[ExportedFile]) {
+    for file in files.iter().filter(|file| !file.pypi) {
+        tracing::info!("{}", size_summary(file));
+    }
+}
+
+/// Returns true if a line of an explicit spec is a package entry.
+fn is_spec_entry(line: &str) -> bool {
+    !line.is_empty() && !line.starts_with('#') && !line.starts_with('@')
+}
+
+/// Returns the platform of the `# platform: ` line of an explicit spec.
+fn spec_platform(contents: &str) -> Option<&str> {
+    contents
+        .lines()
+        .find_map(|line| line.strip_prefix("# platform: "))
+        .map(str::trim)
+}
+
+/// Merges the package entries of an existing explicit spec into a new one and
+/// returns the merged spec with the number of entries that were added. The
+/// entries are deduplicated by their url without the hash fragment, keeping
+/// the new entry, and sorted in the `order` of a fresh export. Without a sort
+/// key the existing entries follow the new ones. The lines above and below
+/// the entries of the new spec are kept as-is.
+fn merge_explicit_spec<'a>(
+    existing: &'a str,
+    new: &'a str,
+    order: PackageOrder,
+) -> (String, usize) {
+    let lines = new.lines().collect_vec();
+    let (header, mut entries, trailer) = match (
+        lines.iter().position(|line| is_spec_entry(line)),
+        lines.iter().rposition(|line| is_spec_entry(line)),
+    ) {
+        (Some(first), Some(last)) => (
+            &lines[..first],
+            lines[first..=last]
+                .iter()
+                .copied()
+                .filter(|line| is_spec_entry(line))
+                .collect_vec(),
+            &lines[last + 1..],
+        ),
+        _ => (lines.as_slice(), Vec::new(), &[][..]),
+    };
+
+    let url_of = |line: &str| line.split('#').next().unwrap_or_default().to_string();
+    let mut seen: HashSet<_> = entries.iter().map(|line| url_of(line)).collect();
+    let mut added = 0;
+    for line in existing
+        .lines()
+        .map(str::trim)
+        .filter(|line| is_spec_entry(line))
+    {
+        if seen.insert(url_of(line)) {
+            entries.push(line);
+            added += 1;
+        }
+    }
+    if let Some(sort_by) = order.sort_by {
+        entries.sort_by_cached_key(|line| {
+            let url = url_of(line);
+            let name = match sort_by {
+                SortKey::Name => Url::parse(&url)
+                    .ok()
+                    .and_then(|url| ArchiveIdentifier::try_from_url(&url))
+                    .map(|identifier| identifier.name)
+                    .unwrap_or_default(),
+                // the sizes of the existing entries are unknown, `--append`
+                // rejects sorting by size
+                SortKey::Url | SortKey::Size => String::new(),
+            };
+            (name, url)
+        });
+    }
+    if order.noarch_last {
+        entries.sort_by_key(|line| url_of(line).contains("/noarch/"));
+    }
+
+    let merged: String = header
+        .iter()
+        .chain(&entries)
+        .chain(trailer)
+        .map(|line| format!("{}\n", line))
+        .collect();
+    (merged, added)
+}
+
+/// Merges the entries of the explicit spec at the target of the file into it
+/// for `--append`, warns if the platforms of the specs differ.
+fn append_existing_spec(file: &mut ExportedFile, order: PackageOrder) -> miette::Result<()> {
+    if !file.path.is_file() {
+        return Ok(());
+    }
+    let existing = fs::read_to_string(&file.path).map_err(|e| {
+        miette::miette!(
+            "Could not read the explicit spec '{}' to append to: {}",
+            file.path.display(),
+            e
+        )
+    })?;
+
+    if let (Some(existing_platform), Some(platform)) =
+        (spec_platform(&existing), spec_platform(&file.contents))
+    {
+        if existing_platform != platform {
+            tracing::warn!(
+                "appending the {} packages to '{}', which contains packages for {}",
+                platform,
+                file.path.display(),
+                existing_platform
+            );
+        }
+    }
+
+    ensure_valid_hash_fragments(&file.path, &existing)?;
+    let (merged, added) = merge_explicit_spec(&existing, &file.contents, order);
+    tracing::info!(
+        "appending {} packages of '{}' to the export",
+        added,
+        file.path.display()
+    );
+    file.contents = merged;
+    file.packages += added;
+    Ok(())
+}
+
+/// Returns the unique package urls of the exported explicit specs, without
+/// their hash fragment.
+fn exported_package_urls(files: &[ExportedFile]) -> miette::Result<Vec<Url>> {
+    let mut urls = Vec::new();
+    for file in files.iter().filter(|file| !file.pypi) {
+        for line in file.contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') || line == "@EXPLICIT" {
+                continue;
+            }
+            let mut url = Url::parse(line).map_err(|e| {
+                miette::miette!("invalid package url '{}' in the export: {}", line, e)
+            })?;
+            url.set_fragment(None);
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    Ok(urls)
+}
+
+/// Checks that the package urls of the exported files are reachable with
+/// concurrent HEAD requests, local files only have to exist. Errors listing
+/// all urls that failed.
+async fn verify_package_urls(
+    client: &ClientWithMiddleware,
+    files: &[ExportedFile],
+    timeout: Duration,
+    show_progress: bool,
+) -> miette::Result<()> {
+    let urls = exported_package_urls(files)?;
+    tracing::info!("verifying {} package urls", urls.len());
+
+    let pb = export_progress_bar(show_progress, "verifying urls", urls.len());
+    let failures = futures::stream::iter(urls)
+        .map(|url| async move {
+            let result = match url.scheme() {
+                "file" => match url.to_file_path() {
+                    Ok(path) if path.is_file() => Ok(()),
+                    _ => Err("file does not exist".to_string()),
+                },
+                _ => match client.head(url.clone()).timeout(timeout).send().await {
+                    Ok(response) if response.status().is_success() => Ok(()),
+                    Ok(response) => Err(response.status().to_string()),
+                    Err(e) => Err(e.to_string()),
+                },
+            };
+            result.err().map(|reason| format!("{} ({})", url, reason))
+        })
+        .buffer_unordered(50)
+        .inspect(|_| pb.inc(1))
+        .filter_map(|failure| async move { failure })
+        .collect::<Vec<_>>()
+        .await;
+    pb.finish_and_clear();
+
+    if !failures.is_empty() {
+        miette::bail!(
+            code = "url-not-reachable",
+            "the following package urls are not reachable: {}",
+            failures.iter().sorted().format(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Writes the exported files concurrently, every file is written on a
+/// blocking thread.
+async fn write_export_files(files: &[ExportedFile]) -> miette::Result<()> {
+    let mut writes = files
+        .iter()
+        .map(|file| {
+            let path = file.path.clone();
+            let contents = file.contents.clone();
+            tokio::task::spawn_blocking(move || {
+                write_export_file(&path, &contents)
+                    .map_err(|e| miette::miette!("Could not write '{}': {}", path.display(), e))
+            })
+        })
+        .collect::<FuturesUnordered<_>>();
+
+    while let Some(result) = writes.next().await {
+        match result {
+            Ok(result) => result?,
+            Err(e) => {
+                if let Ok(panic) = e.try_into_panic() {
+                    std::panic::resume_unwind(panic);
+                }
+                miette::bail!("writing the exported files was cancelled");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns the `sha256sum` compatible checksum line of a written file.
+fn checksum_line(path: &Path) -> miette::Result<String> {
+    let digest = compute_file_digest::<Sha256>(path).into_diagnostic()?;
+    Ok(format!("{:x}  {}", digest, path.display()))
+}
+
+/// A file of the export. All files are built before any of them is written.
+#[derive(Debug, Serialize)]
+struct ExportedFile {
+    path: PathBuf,
+    /// The platform of the file, `None` for a merged file of all platforms.
+    platform: Option<Platform>,
+    environment: String,
+    packages: usize,
+    /// Whether the file contains the pypi packages instead of the conda
+    /// packages.
+    #[serde(skip)]
+    pypi: bool,
+    #[serde(skip)]
+    contents: String,
+    /// The download sizes of the conda packages of the file per channel,
+    /// empty for the pypi files.
+    #[serde(skip)]
+    sizes: BTreeMap<String, ChannelSize>,
+}
+
+/// The json report of the files that were written by the export.
+#[derive(Debug, Serialize)]
+struct ExportReport<'a> {
+    files: &'a [ExportedFile],
+}
+
+fn write_report(target: &Path, files: &[ExportedFile]) -> miette::Result<()> {
+    let contents = serde_json::to_string_pretty(&ExportReport { files }).into_diagnostic()?;
+    fs::write(target, contents)
+        .map_err(|e| miette::miette!("Could not write report '{}': {}", target.display(), e))
+}
+
+/// Runs the post-export command for an exported file through the shell of
+/// the platform, errors if the command fails.
+fn run_post_export_command(command: &str, file: &ExportedFile) -> miette::Result<()> {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = std::process::Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    };
+    cmd.env("PIXI_EXPORT_FILE", &file.path)
+        .env("PIXI_EXPORT_ENVIRONMENT", &file.environment);
+    // A merged file has no single platform
+    match file.platform {
+        Some(platform) => cmd.env("PIXI_EXPORT_PLATFORM", platform.as_str()),
+        None => cmd.env_remove("PIXI_EXPORT_PLATFORM"),
+    };
+
+    let status = cmd
+        .status()
+        .map_err(|e| miette::miette!("Could not run post-export command `{}`: {}", command, e))?;
+    if !status.success() {
+        miette::bail!(
+            code = "post-export-command-failed",
+            "post-export command `{}` failed for '{}' with {}",
+            command,
+            file.path.display(),
+            status
+        );
+    }
+    Ok(())
+}
+
+/// Returns the current working directory, the default output directory of
+/// the exports.
+pub(super) fn cwd() -> miette::Result<PathBuf> {
+    std::env::current_dir()
+        .map_err(|e| miette::miette!("Could not obtain the current working directory: {}", e))
+}
+
+/// Returns true if an url fragment (`<md5>`, `md5:<md5>` or `sha256:<sha256>`)
+/// matches the corresponding hash of the record.
+fn fragment_matches_record(fragment: &str, record: &PackageRecord) -> bool {
+    let (algorithm, hash) = fragment.split_once(':').unwrap_or(("md5", fragment));
+    match algorithm {
+        "md5" => record
+            .md5
+            .as_ref()
+            .is_some_and(|md5| format!("{:x}", md5).eq_ignore_ascii_case(hash)),
+        "sha256" => record
+            .sha256
+            .as_ref()
+            .is_some_and(|sha256| format!("{:x}", sha256).eq_ignore_ascii_case(hash)),
+        _ => false,
+    }
+}
+
+/// Returns true if an url fragment of an explicit spec is a hash conda
+/// accepts: a hex md5 hash, optionally prefixed with `md5:`, or a hex sha256
+/// hash prefixed with `sha256:`.
+fn is_valid_hash_fragment(fragment: &str) -> bool {
+    let (hash, len) = match fragment.split_once(':') {
+        Some(("md5", hash)) => (hash, 32),
+        Some(("sha256", hash)) => (hash, 64),
+        Some(_) => return false,
+        None => (fragment, 32),
+    };
+    hash.len() == len && hash.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Errors with the entries of an explicit spec that have a hash fragment
+/// conda would reject, so `--append` doesn't carry them into the export.
+fn ensure_valid_hash_fragments(path: &Path, contents: &str) -> miette::Result<()> {
+    let invalid = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| is_spec_entry(line))
+        .filter(|line| {
+            line.split_once('#')
+                .is_some_and(|(_, fragment)| !is_valid_hash_fragment(fragment))
+        })
+        .collect_vec();
+    if !invalid.is_empty() {
+        miette::bail!(
+            code = "invalid-hash",
+            help = "fix or remove these entries, or export without `--append`",
+            "the explicit spec '{}' has entries with an invalid hash: {}",
+            path.display(),
+            invalid.iter().format(", ")
+        );
+    }
+    Ok(())
+}
+
+/// The order of the packages in the exported files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackageOrder {
+    /// The key to sort the packages by so that the output does not depend on
+    /// the order of the packages in the lock file, `None` keeps the lock-file
+    /// order.
+    pub sort_by: Option<SortKey>,
+    /// Move the noarch packages after the packages of the platform subdir,
+    /// like conda orders the packages of a solve.
+    pub noarch_last: bool,
+}
+
+impl Default for PackageOrder {
+    fn default() -> Self {
+        Self {
+            sort_by: Some(SortKey::default()),
+            noarch_last: true,
+        }
+    }
+}
+
+impl PackageOrder {
+    /// Returns the packages in this order, packages that compare equal keep
+    /// their lock-file order.
+    fn apply<'a>(
+        self,
+        conda_packages: impl IntoIterator<Item = &'a CondaPackage>,
+    ) -> Vec<&'a CondaPackage> {
+        let mut packages = conda_packages.into_iter().collect_vec();
+        if let Some(sort_by) = self.sort_by {
+            packages.sort_by(|a, b| {
+                let (a_record, b_record) = (a.package_record(), b.package_record());
+                match sort_by {
+                    SortKey::Name => a_record
+                        .name
+                        .as_normalized()
+                        .cmp(b_record.name.as_normalized()),
+                    SortKey::Url => std::cmp::Ordering::Equal,
+                    // packages of unknown size sort last
+                    SortKey::Size => (a_record.size.is_none(), a_record.size)
+                        .cmp(&(b_record.size.is_none(), b_record.size)),
+                }
+                .then_with(|| a.url().as_str().cmp(b.url().as_str()))
+            });
+        }
+        if self.noarch_last {
+            packages.sort_by_key(|p| p.package_record().subdir == Platform::NoArch.as_str());
+        }
+        packages
+    }
+}
+
+/// Checks that the conda packages of an explicit spec for `platform` are from
+/// its subdir or `noarch`, since conda installs them regardless of the
+/// `@EXPLICIT` platform. Packages of another subdir are an error with
+/// `strict`, otherwise a warning.
+fn check_spec_platform<'a>(
+    platform: Platform,
+    conda_packages: impl IntoIterator<Item = &'a CondaPackage>,
+    strict: bool,
+) -> miette::Result<()> {
+    let conda_packages = conda_packages.into_iter().collect_vec();
+    let mismatched = conda_packages
+        .iter()
+        .map(|p| p.package_record())
+        .filter(|record| {
+            record.subdir != platform.as_str() && record.subdir != Platform::NoArch.as_str()
+        })
+        .map(|record| format!("{} ({})", record.name.as_normalized(), record.subdir))
+        .collect_vec();
+
+    if mismatched.is_empty() {
+        if !conda_packages.is_empty() && platform != Platform::NoArch {
+            let noarch_only = conda_packages
+                .iter()
+                .all(|p| p.package_record().subdir == Platform::NoArch.as_str());
+            if noarch_only {
+                tracing::debug!(
+                    "the explicit spec for {} only contains noarch packages",
+                    platform
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    if strict {
+        miette::bail!(
+            code = "platform-mismatch",
+            help = "remove `--strict-platform` to export the spec anyway",
+            "the explicit spec for {} contains packages of another platform: {}",
+            platform,
+            mismatched.iter().format(", ")
+        );
+    }
+    tracing::warn!(
+        "the explicit spec for {} contains packages of another platform: {}",
+        platform,
+        mismatched.iter().format(", ")
+    );
+    Ok(())
+}
+
+/// Builds the explicit spec from the conda packages in the order of the
+/// options. The channel overrides are applied to the urls before the hash
+/// fragment is set.
+fn build_explicit_spec<'a>(
+    platform: Platform,
+    conda_packages: impl IntoIterator<Item = &'a CondaPackage>,
+    options: &ExplicitSpecOptions,
+) -> miette::Result<ExplicitEnvironmentSpec> {
+    let mut packages = Vec::new();
+    let mut disallowed = Vec::new();
+    let mut stripped = 0;
+    let mut seen = HashSet::new();
+    let mut duplicates = Vec::new();
+
+    let conda_packages = options.order.apply(conda_packages);
+    check_spec_platform(
+        platform,
+        conda_packages.iter().copied(),
+        options.strict_platform,
+    )?;
+
+    let pb = export_progress_bar(
+        options.show_progress,
+        "building explicit spec",
+        conda_packages.len(),
+    );
+    for cp in conda_packages {
+        pb.inc(1);
+        let prec = cp.package_record();
+        let mut url = override_channel(cp.url(), &options.channel_overrides)?;
+        if options.strip_query && url.query().is_some() {
+            url.set_query(None);
+            stripped += 1;
+        }
+        // conda fails on the same url listed twice, the fragment is replaced
+        // by the hash below so it doesn't make an entry distinct
+        let mut key = url.clone();
+        key.set_fragment(None);
+        if !seen.insert(key.clone()) && !options.no_dedup {
+            duplicates.push(key);
+            continue;
+        }
+        ensure_secure_url(&url, prec, options.deny_insecure)?;
+        if !is_allowed_channel(&url, &options.allowed_channels) {
+            disallowed.push(format!("{} ({})", prec.name.as_normalized(), url));
+        }
+        // Prefer md5 for compatibility with older conda clients, but fall back
+        // to sha256 which conda also accepts as `#sha256:<hash>`.
+        let fragment = match (&prec.md5, &prec.sha256) {
+            (Some(md5), _) => format!("{:x}", md5),
+            (None, _) if options.md5_only => miette::bail!(
+                code = "missing-md5",
+                help = "remove `--md5-only` to fall back to the sha256 hash",
+                "Package {} does not contain an md5 hash",
+                prec.name.as_normalized()
+            ),
+            (None, Some(sha256)) => format!("sha256:{:x}", sha256),
+            (None, None) => miette::bail!(
+                code = "missing-hash",
+                "Package {} does not contain an md5 or sha256 hash",
+                prec.name.as_normalized()
+            ),
+        };
+        // conda rejects the whole spec if a fragment is not a valid hash
+        if !is_valid_hash_fragment(&fragment) {
+            miette::bail!(
+                code = "invalid-hash",
+                "the hash of package {} is not a valid md5 or sha256 hash: {}, the lock file may be corrupt",
+                prec.name.as_normalized(),
+                fragment
+            );
+        }
+
+        // Keep a fragment that is already present on the url if it refers to
+        // one of the hashes of the record, a sha256 one is replaced with
+        // `--md5-only`.
+        match url.fragment().map(ToOwned::to_owned) {
+            Some(existing)
+                if fragment_matches_record(&existing, prec)
+                    && !(options.md5_only && existing.starts_with("sha256:")) => {}
+            Some(existing) => {
+                tracing::warn!(
+                    "overwriting fragment '{}' of {} with '{}' since it does not match the hash of the package",
+                    existing,
+                    prec.name.as_normalized(),
+                    fragment
+                );
+                url.set_fragment(Some(&fragment));
+            }
+            None => url.set_fragment(Some(&fragment)),
+        }
+
+        packages.push(ExplicitEnvironmentEntry { url });
+    }
+    pb.finish_and_clear();
+
+    if !duplicates.is_empty() {
+        tracing::warn!(
+            "removed {} duplicate package entries from the explicit spec: {}",
+            duplicates.len(),
+            duplicates.iter().format(", ")
+        );
+    }
+
+    if stripped > 0 {
+        tracing::warn!(
+            "removed the query of {} package urls, if it contained auth tokens the spec is not installable without separate credentials",
+            stripped
+        );
+    }
+
+    if !disallowed.is_empty() {
+        miette::bail!(
+            code = "channel-not-allowed",
+            help = format!(
+                "the allowed channels are: {}",
+                options.allowed_channels.iter().format(", ")
+            ),
+            "the following packages are not from an allowed channel: {}",
+            disallowed.iter().format(", ")
+        );
+    }
+
+    Ok(ExplicitEnvironmentSpec {
+        platform: Some(platform),
+        packages,
+    })
+}
+
+/// A conda package in the json export.
+#[derive(Debug, Serialize)]
+struct JsonPackage {
+    name: String,
+    version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build: Option<String>,
+    url: String,
+    md5: Option<String>,
+    sha256: Option<String>,
+}
+
+/// The json export of the conda packages of an environment for a single
+/// platform.
+#[derive(Debug, Serialize)]
+struct JsonSpec {
+    platform: Platform,
+    environment: String,
+    packages: Vec<JsonPackage>,
+}
+
+/// Builds the json export from the conda packages in the given order. The
+/// build strings are omitted unless `include_build_string` is set.
+fn build_json_spec<'a>(
+    environment_name: &str,
+    platform: Platform,
+    conda_packages: impl IntoIterator<Item = &'a CondaPackage>,
+    order: PackageOrder,
+    include_build_string: bool,
+    channel_overrides: &[ChannelOverride],
+) -> miette::Result<JsonSpec> {
+    let packages = order
+        .apply(conda_packages)
+        .into_iter()
+        .map(|cp| {
+            let prec = cp.package_record();
+            Ok(JsonPackage {
+                name: prec.name.as_normalized().to_string(),
+                version: prec.version.to_string(),
+                build: include_build_string.then(|| prec.build.clone()),
+                url: override_channel(cp.url(), channel_overrides)?.to_string(),
+                md5: prec.md5.as_ref().map(|md5| format!("{:x}", md5)),
+                sha256: prec.sha256.as_ref().map(|sha256| format!("{:x}", sha256)),
+            })
+        })
+        .collect::<miette::Result<Vec<_>>>()?;
+
+    Ok(JsonSpec {
+        platform,
+        environment: environment_name.to_string(),
+        packages,
+    })
+}
+
+/// Returns true if the url starts with one of the allowed prefixes, or if
+/// there are no allowed prefixes.
+fn is_allowed_channel(url: &Url, allowed_channels: &[Url]) -> bool {
+    allowed_channels.is_empty()
+        || allowed_channels
+            .iter()
+            .any(|prefix| url.as_str().starts_with(prefix.as_str()))
+}
+
+/// Warns about a package that is downloaded over plain `http`, or fails if
+/// `deny_insecure` is set.
+fn ensure_secure_url(url: &Url, record: &PackageRecord, deny_insecure: bool) -> miette::Result<()> {
+    if url.scheme() != "http" {
+        return Ok(());
+    }
+    if deny_insecure {
+        miette::bail!(
+            code = "insecure-url",
+            help = "use an https mirror with `--channel-override`, or don't pass `--deny-insecure`",
+            "package {} is downloaded over insecure http: {}",
+            record.name.as_normalized(),
+            url
+        );
+    }
+    tracing::warn!(
+        "package {} is downloaded over insecure http: {}",
+        record.name.as_normalized(),
+        url
+    );
+    Ok(())
+}
+
+/// Builds the explicit specs for a merged export of multiple platforms. Noarch
+/// packages that are locked for every platform are moved to a leading
+/// `noarch` spec instead of being repeated in the spec of every platform.
+fn build_merged_explicit_specs(
+    platform_packages: &[(Platform, Vec<CondaPackage>)],
+    options: &ExplicitSpecOptions,
+) -> miette::Result<Vec<ExplicitEnvironmentSpec>> {
+    let shared: HashSet<_> = match platform_packages {
+        [(_, first), rest @ ..] if !rest.is_empty() => first
+            .iter()
+            .filter(|p| p.package_record().subdir == Platform::NoArch.as_str())
+            .map(|p| p.url())
+            .filter(|url| {
+                rest.iter()
+                    .all(|(_, packages)| packages.iter().any(|p| p.url() == *url))
+            })
+            .collect(),
+        _ => HashSet::new(),
+    };
+
+    let mut specs = Vec::new();
+    if let [(_, first), ..] = platform_packages {
+        if !shared.is_empty() {
+            let noarch = first.iter().filter(|p| shared.contains(p.url()));
+            specs.push(build_explicit_spec(Platform::NoArch, noarch, options)?);
+        }
+    }
+    for (platform, packages) in platform_packages {
+        let packages = packages.iter().filter(|p| !shared.contains(p.url()));
+        specs.push(build_explicit_spec(*platform, packages, options)?);
+    }
+
+    Ok(specs)
+}
+
+/// The comment at the top of the exported explicit specs.
+pub(super) const GENERATED_BY_HEADER: &str = "# Generated by `pixi project export`\n";
+
+/// Parses the seconds since the unix epoch of `SOURCE_DATE_EPOCH`.
+fn parse_source_date_epoch(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(value.trim().parse().ok()?, 0)
+}
+
+/// Returns the time of an export, which is computed once per export so all
+/// its files get the same time. The value of the `SOURCE_DATE_EPOCH`
+/// environment variable takes precedence over the current time, so builds
+/// that set it get reproducible files.
+fn export_time(source_date_epoch: Option<&str>) -> DateTime<Utc> {
+    match source_date_epoch {
+        Some(value) => parse_source_date_epoch(value).unwrap_or_else(|| {
+            tracing::warn!(
+                "ignoring SOURCE_DATE_EPOCH='{}', it is not a number of seconds since the unix epoch",
+                value
+            );
+            Utc::now()
+        }),
+        None => Utc::now(),
+    }
+}
+
+/// Returns the comment lines to write above the explicit specs of an
+/// environment: unless `--no-header` is set the generated-by header followed
+/// by the name of the environment, the version of pixi and with `--timestamp`
+/// the time of the export and, with `--annotate`, the channels of the
+/// environment in order of priority.
+fn explicit_spec_header(
+    args: &Args,
+    environment: &Environment<'_>,
+    generated_at: DateTime<Utc>,
+) -> String {
+    let mut header = String::new();
+    if !args.no_header {
+        header.push_str(GENERATED_BY_HEADER);
+        header.push_str(&format!(
+            "# environment: {}\n# pixi-version: {}\n",
+            environment.name(),
+            consts::PIXI_VERSION
+        ));
+        if args.timestamp {
+            header.push_str(&format!(
+                "# generated-at: {}\n",
+                generated_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+            ));
+        }
+    }
+    if args.annotate {
+        let channel_config = environment.project().channel_config();
+        for channel in environment.channels() {
+            header.push_str(&format!(
+                "# channel: {}\n",
+                channel.clone().into_base_url(&channel_config)
+            ));
+        }
+    }
+    header
+}
+
+/// Renders the explicit specs after each other, prefixed with the header.
+fn render_explicit_specs(specs: &[ExplicitEnvironmentSpec], header: &str) -> String {
+    let mut environment = header.to_string();
+    environment.push_str(
+        specs
+            .iter()
+            .map(ExplicitEnvironmentSpec::to_spec_string)
+            .join("\n")
+            .as_str(),
+    );
+    environment
+}
+
+/// Renders the explicit spec, prefixed with the header.
+pub(super) fn render_explicit_spec(exp_env_spec: &ExplicitEnvironmentSpec, header: &str) -> String {
+    render_explicit_specs(std::slice::from_ref(exp_env_spec), header)
+}
+
+/// Appends the pip requirements of the pypi packages to the rendered explicit
+/// spec at `target` as `# pypi: ` comments if `--pypi-inline` is given.
+fn inline_pypi_requirements(
+    args: &Args,
+    environment: &Environment<'_>,
+    target: &Path,
+    mut contents: String,
+    pypi_packages: &[PypiPackageData],
+) -> miette::Result<String> {
+    if !args.pypi_inline || pypi_packages.is_empty() {
+        return Ok(contents);
+    }
+
+    let requirements = render_pypi_requirements(
+        target,
+        pypi_packages,
+        args.pypi_hash_algo,
+        environment.project().root(),
+        args.relative_paths,
+        None,
+        &[],
+        &[],
+    )?;
+    for line in requirements.lines() {
+        contents.push_str(&format!("# pypi: {}\n", line));
+    }
+    Ok(contents)
+}
+
+/// Returns the `--annotate-packages` comment of a package.
+fn package_annotation(record: &PackageRecord) -> String {
+    let mut annotation = format!(
+        "# {} {} license={}",
+        record.name.as_normalized(),
+        record.version,
+        record.license.as_deref().unwrap_or("unknown")
+    );
+    if let Some(size) = record.size {
+        annotation.push_str(&format!(" size={}", size));
+    }
+    annotation
+}
+
+/// Adds the `--annotate-packages` and the `--annotate` subdir comments of
+/// every package above its entry in the rendered explicit specs, and groups
+/// the entries by channel with `--group-by-channel`. The entries are matched
+/// by their url without the hash fragment. The contents are returned
+/// unchanged without the flags.
+fn annotate_packages<'a>(
+    args: &Args,
+    contents: String,
+    conda_packages: impl IntoIterator<Item = &'a CondaPackage>,
+) -> miette::Result<String> {
+    if !args.annotate_packages && !args.annotate {
+        return Ok(match args.group_by_channel {
+            true => group_by_channel(&contents),
+            false => contents,
+        });
+    }
+
+    let mut annotations = HashMap::new();
+    for package in conda_packages {
+        let mut url = override_channel(package.url(), &args.channel_override)?;
+        url.set_fragment(None);
+        if args.strip_query {
+            url.set_query(None);
+        }
+        let record = package.package_record();
+        let mut annotation = Vec::new();
+        if args.annotate_packages {
+            annotation.push(package_annotation(record));
+        }
+        if args.annotate {
+            annotation.push(format!("# subdir: {}", record.subdir));
+        }
+        annotations.insert(url.to_string(), annotation.join("\n"));
+    }
+
+    let contents: String = contents
+        .split_inclusive('\n')
+        .map(|line| {
+            let url = line.trim_end().split('#').next().unwrap_or_default();
+            match annotations.get(url) {
+                Some(annotation) => format!("{}\n{}", annotation, line),
+                None => line.to_string(),
+            }
+        })
+        .collect();
+    Ok(match args.group_by_channel {
+        true => group_by_channel(&contents),
+        false => contents,
+    })
+}
+
+/// Returns the channel of a package url in an explicit spec, which is the url
+/// without the hash fragment, the subdir and the file name.
+pub(super) fn channel_of_entry(url: &str) -> &str {
+    let url = url.split('#').next().unwrap_or_default();
+    url.rsplitn(3, '/').nth(2).unwrap_or(url)
+}
+
+/// Reorders the package entries of every explicit spec in the rendered
+/// contents by their channel, in the order the channels first appear. Every
+/// group is preceded by a `# --- channel: <url> ---` comment, and the comment
+/// lines directly above an entry move with it.
+fn group_by_channel(contents: &str) -> String {
+    let mut grouped = String::new();
+    let mut groups: Vec<(&str, String)> = Vec::new();
+    let mut pending = String::new();
+
+    let flush = |grouped: &mut String, groups: &mut Vec<(&str, String)>| {
+        for (channel, entries) in groups.drain(..) {
+            grouped.push_str(&format!("# --- channel: {} ---\n{}", channel, entries));
+        }
+    };
+
+    for line in contents.split_inclusive('\n') {
+        if line.starts_with('#') {
+            pending.push_str(line);
+            continue;
+        }
+        if line.starts_with('@') || !line.contains("://") {
+            flush(&mut grouped, &mut groups);
+            grouped.push_str(&pending);
+            pending.clear();
+            grouped.push_str(line);
+            continue;
+        }
+
+        let channel = channel_of_entry(line.trim_end());
+        let entry = format!("{}{}", std::mem::take(&mut pending), line);
+        match groups.iter_mut().find(|(c, _)| *c == channel) {
+            Some((_, entries)) => entries.push_str(&entry),
+            None => groups.push((channel, entry)),
+        }
+    }
+    flush(&mut grouped, &mut groups);
+    grouped.push_str(&pending);
+    grouped
+}
+
+fn get_pypi_hash_str(
+    package_data: &PypiPackageData,
+    algorithm: PypiHashAlgorithm,
+) -> Option<String> {
+    let hashes = package_data.hash.as_ref()?;
+    let hash = match (algorithm, hashes) {
+        (PypiHashAlgorithm::None, _) => return None,
+        (PypiHashAlgorithm::Sha256, PackageHashes::Sha256(h))
+        | (PypiHashAlgorithm::Sha256, PackageHashes::Md5Sha256(_, h)) => {
+            format!("--hash=sha256:{:x}", h)
+        }
+        (PypiHashAlgorithm::Md5, PackageHashes::Md5(h))
+        | (PypiHashAlgorithm::Md5, PackageHashes::Md5Sha256(h, _)) => {
+            format!("--hash=md5:{:x}", h)
+        }
+        (PypiHashAlgorithm::Sha256, PackageHashes::Md5(h)) => {
+            tracing::warn!(
+                "no sha256 hash available for {}, using md5 instead",
+                package_data.name
+            );
+            format!("--hash=md5:{:x}", h)
+        }
+        (PypiHashAlgorithm::Md5, PackageHashes::Sha256(h)) => {
+            tracing::warn!(
+                "no md5 hash available for {}, using sha256 instead",
+                package_data.name
+            );
+            format!("--hash=sha256:{:x}", h)
+        }
+    };
+    Some(hash)
+}
+
+/// Errors if any of the pypi packages that are installed from an url has no
+/// hash, listing all offending packages.
+fn ensure_pypi_hashes(packages: &[PypiPackageData]) -> miette::Result<()> {
+    let missing = packages
+        .iter()
+        .filter(|p| matches!(p.url_or_path, UrlOrPath::Url(_)) && p.hash.is_none())
+        .map(|p| p.name.to_string())
+        .collect_vec();
+    if !missing.is_empty() {
+        miette::bail!(
+            code = "missing-pypi-hash",
+            "the following PyPI packages have no hash: {}",
+            missing.iter().format(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Returns the path of a local package relative to `base`. Paths in the lock
+/// file are relative to the project root. Falls back to the path as-is if no
+/// relative path can be computed, e.g. when it is on a different drive.
+pub(super) fn relative_package_path(path: &Path, project_root: &Path, base: &Path) -> PathBuf {
+    let absolute = project_root.join(path);
+    if absolute.components().next() != base.components().next() {
+        return path.to_path_buf();
+    }
+    pathdiff::diff_paths(&absolute, base).unwrap_or_else(|| path.to_path_buf())
+}
+
+/// Returns true for pypi packages that are installed from a local path, these
+/// can't be pinned by version.
+fn is_local_pypi_package(package: &PypiPackageData) -> bool {
+    package.editable || matches!(package.url_or_path, UrlOrPath::Path(_))
+}
+
+/// Returns true for pypi packages that are built from source when installed,
+/// i.e. everything but wheels.
+fn is_source_pypi_package(package: &PypiPackageData) -> bool {
+    let file_name = match &package.url_or_path {
+        UrlOrPath::Url(url) => url
+            .path_segments()
+            .and_then(|segments| segments.last())
+            .unwrap_or_default()
+            .to_string(),
+        UrlOrPath::Path(path) => path.to_string_lossy().into_owned(),
+    };
+    !file_name.ends_with(".whl")
+}
+
+/// Returns the PEP 503 canonical form of a PyPI package name, which is
+/// lowercase with every run of `-`, `_` and `.` replaced by a single `-`.
+fn canonical_pypi_name(name: &str) -> String {
+    name.split(['-', '_', '.'])
+        .filter(|part| !part.is_empty())
+        .join("-")
+        .to_lowercase()
+}
+
+/// Returns the key to sort a line of a pip requirements file by, which is the
+/// final segment of the url or path of the package.
+fn requirement_sort_key(package: &PypiPackageData) -> String {
+    let segment = match &package.url_or_path {
+        UrlOrPath::Url(url) => url
+            .path_segments()
+            .and_then(|segments| segments.filter(|s| !s.is_empty()).last())
+            .unwrap_or_default()
+            .to_string(),
+        UrlOrPath::Path(path) => path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    };
+    segment.to_lowercase()
+}
+
+/// Returns the pypi packages sorted by `key`, or in lock-file order with
+/// `--no-sort-pypi`.
+fn sorted_pypi_packages<K: Ord>(
+    args: &Args,
+    packages: &[PypiPackageData],
+    key: impl Fn(&PypiPackageData) -> K,
+) -> Vec<PypiPackageData> {
+    let mut packages = packages.to_vec();
+    if !args.no_sort_pypi {
+        packages.sort_by_key(|p| key(p));
+    }
+    packages
+}
+
+/// Renders the `name==version` pins of the pypi packages as a pip constraints
+/// file, with the names in their canonical form. Local packages are omitted
+/// since they can't be pinned by version.
+fn render_pypi_constraints(packages: &[PypiPackageData]) -> String {
+    let mut constraints = String::new();
+
+    for p in packages {
+        if is_local_pypi_package(p) {
+            tracing::debug!(
+                "omitting local PyPI package {} from the constraints",
+                p.name
+            );
+            continue;
+        }
+        constraints.push_str(&format!(
+            "{}=={}\n",
+            canonical_pypi_name(&p.name.to_string()),
+            p.version
+        ));
+    }
+
+    constraints
+}
+
+/// Renders the pypi packages as a pip requirements file at `target`,
+/// preceded by the `--index-url` and `--extra-index-url` lines of the given
+/// indexes. Local package paths are relative to the `project_root`, with
+/// `relative_paths` they are rewritten relative to the directory of the
+/// requirements file.
+#[allow(clippy::too_many_arguments)]
+pub(super) fn render_pypi_requirements(
+    target: &Path,
+    packages: &[PypiPackageData],
+    hash_algorithm: PypiHashAlgorithm,
+    project_root: &Path,
+    relative_paths: bool,
+    index_url: Option<&Url>,
+    extra_index_urls: &[Url],
+    pip_options: &[String],
+) -> miette::Result<String> {
+    let target_dir = match relative_paths {
+        true => Some(cwd()?.join(target.parent().unwrap_or(Path::new("")))),
+        false => None,
+    };
+    let mut reqs = String::new();
+
+    for pip_option in pip_options {
+        reqs.push_str(&format!("{}\n", pip_option));
+    }
+    if let Some(index_url) = index_url {
+        reqs.push_str(&format!("--index-url {}\n", index_url));
+    }
+    for extra_index_url in extra_index_urls {
+        reqs.push_str(&format!("--extra-index-url {}\n", extra_index_url));
+    }
+
+    for p in packages {
+        // pip --verify-hashes does not accept hashes for local files
+        let (s, include_hash) = match &p.url_or_path {
+            UrlOrPath::Url(url) => (url.to_string(), true),
+            UrlOrPath::Path(path) => {
+                let location = project_root.join(path);
+                let path = match &target_dir {
+                    Some(target_dir) => relative_package_path(path, project_root, target_dir),
+                    None => path.clone(),
+                };
+                let path = path.into_os_string().into_string().map_err(|path| {
+                    miette::miette!(
+                        "the path of PyPI package {} is not valid UTF-8: {:?}",
+                        p.name,
+                        path
+                    )
+                })?;
+                // pip mishandles backslashes in editable paths in some
+                // contexts, forward slashes work on all platforms
+                let path = if p.editable {
+                    path.replace('\\', "/")
+                } else {
+                    path
+                };
+                (escape_requirement_path(p, path, &location)?, false)
+            }
+        };
+
+        let s = strip_direct_prefix(p, &s);
+
+        let hash = match (include_hash, get_pypi_hash_str(p, hash_algorithm)) {
+            (true, Some(h)) => format!(" {}", h),
+            (false, _) | (_, None) => "".to_string(),
+        };
+
+        // pip requires a space between an url and its markers
+        let marker = match &p.requires_python {
+            Some(requires_python) if !requires_python.is_empty() => {
+                format!(" ; {}", python_marker(requires_python))
+            }
+            _ => "".to_string(),
+        };
+
+        if p.editable {
+            reqs.push_str(&format!("-e {}{}{}\n", s, marker, hash));
+        } else {
+            reqs.push_str(&format!("{}{}{}\n", s, marker, hash));
+        }
+    }
+
+    Ok(reqs)
+}
+
+/// Removes the "direct+" prefix of the url of a direct url dependency since
+/// it is not valid for pip urls. Only a single prefix is stripped so a
+/// malformed url is not silently rewritten further.
+pub(super) fn strip_direct_prefix<'a>(package: &PypiPackageData, url: &'a str) -> &'a str {
+    match url.strip_prefix("direct+") {
+        Some(stripped) => {
+            tracing::debug!(
+                "stripped the 'direct+' prefix from the url of {}: {}",
+                package.name,
+                stripped
+            );
+            stripped
+        }
+        None => url,
+    }
+}
+
+/// Escapes a local package path that pip can't parse as-is in a requirements
+/// file, like a path with spaces. An editable path is an option that pip
+/// splits like a shell, so it is quoted. Other requirements are not split, so
+/// the path is written as a percent-encoded `file://` url of its `location`
+/// instead, the path joined to the project root it is relative to in the lock
+/// file, since a file url can't be relative.
+fn escape_requirement_path(
+    package: &PypiPackageData,
+    path: String,
+    location: &Path,
+) -> miette::Result<String> {
+    let needs_escaping = path
+        .chars()
+        .any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '#' | ';'));
+    if !needs_escaping {
+        return Ok(path);
+    }
+    if package.editable {
+        return Ok(format!("\"{}\"", path.replace('"', "\\\"")));
+    }
+
+    if Path::new(&path).is_relative() {
+        tracing::warn!(
+            "writing the path of PyPI package {} as an absolute file url since pip can't parse it as a relative path: {}",
+            package.name,
+            path
+        );
+    }
+    Url::from_file_path(location)
+        .map(String::from)
+        .map_err(|_| {
+            miette::miette!(
+                "the path of PyPI package {} can't be written as a file url: {}",
+                package.name,
+                location.display()
+            )
+        })
+}
+
+/// Converts the `requires-python` specifiers of a package to an environment
+/// marker, e.g. `>=3.8,!=3.9.*` to `python_version >= "3.8" and
+/// python_version != "3.9.*"`. Versions with a patch component are compared
+/// with `python_full_version`.
+fn python_marker(requires_python: &VersionSpecifiers) -> String {
+    requires_python
+        .iter()
+        .map(|specifier| {
+            let version = specifier.version();
+            let variable = if version.release().len() > 2 {
+                "python_full_version"
+            } else {
+                "python_version"
+            };
+            let star = match specifier.operator() {
+                Operator::EqualStar | Operator::NotEqualStar => ".*",
+                _ => "",
+            };
+            format!(
+                "{} {} \"{}{}\"",
+                variable,
+                specifier.operator(),
+                version,
+                star
+            )
+        })
+        .join(" and ")
+}
+
+/// Restricts the conda packages to the ones with the given names, errors if
+/// any of the names is not part of the locked packages.
+fn filter_conda_packages(
+    packages: Vec<CondaPackage>,
+    patterns: &[glob::Pattern],
+    platform: Platform,
+) -> miette::Result<Vec<CondaPackage>> {
+    let matches = |pattern: &glob::Pattern, p: &CondaPackage| {
+        pattern.matches(p.package_record().name.as_normalized())
+    };
+    let missing = patterns
+        .iter()
+        .filter(|pattern| !packages.iter().any(|p| matches(pattern, p)))
+        .map(glob::Pattern::as_str)
+        .collect_vec();
+    if !missing.is_empty() {
+        miette::bail!(
+            code = "package-not-locked",
+            "the following packages are not locked for {}: {}",
+            platform,
+            missing.iter().format(", ")
+        );
+    }
+
+    Ok(packages
+        .into_iter()
+        .filter(|p| patterns.iter().any(|pattern| matches(pattern, p)))
+        .collect())
+}
+
+/// Returns the packages that are reachable from the roots through the
+/// `depends` of the package records, including the roots. Dependencies that
+/// are not locked, like virtual packages, are skipped. Errors if a root is not
+/// locked for the platform.
+fn dependency_closure(
+    packages: Vec<CondaPackage>,
+    roots: &[PackageName],
+    platform: Platform,
+) -> miette::Result<Vec<CondaPackage>> {
+    let missing = roots
+        .iter()
+        .filter(|root| !packages.iter().any(|p| &&p.package_record().name == root))
+        .map(|root| root.as_normalized())
+        .collect_vec();
+    if !missing.is_empty() {
+        miette::bail!(
+            code = "package-not-locked",
+            "the following packages are not locked for {}: {}",
+            platform,
+            missing.iter().format(", ")
+        );
+    }
+
+    let mut reachable = HashSet::new();
+    let mut queue = roots.to_vec();
+    while let Some(name) = queue.pop() {
+        if !reachable.insert(name.clone()) {
+            continue;
+        }
+        let Some(package) = packages.iter().find(|p| p.package_record().name == name) else {
+            tracing::debug!("skipping '{}', it is not locked", name.as_normalized());
+            continue;
+        };
+        for depend in &package.package_record().depends {
+            let spec = MatchSpec::from_str(depend, ParseStrictness::Lenient).into_diagnostic()?;
+            queue.extend(spec.name);
+        }
+    }
+
+    Ok(packages
+        .into_iter()
+        .filter(|p| reachable.contains(&p.package_record().name))
+        .collect())
+}
+
+/// Loads the lock file at `path` for `--lockfile` without updating it, errors
+/// if it doesn't contain the environments to export. Whether it contains the
+/// platforms is checked when they are exported.
+pub(super) fn load_lock_file_from_path(
+    path: &Path,
+    environments: &[Environment<'_>],
+) -> miette::Result<LockFile> {
+    let lock_file = LockFile::from_path(path).map_err(|e| {
+        miette::miette!(
+            code = "lock-file-invalid",
+            "Could not read lock-file '{}': {}",
+            path.display(),
+            e
+        )
+    })?;
+
+    let missing = environments
+        .iter()
+        .map(|environment| environment.name().as_str())
+        .filter(|name| lock_file.environment(name).is_none())
+        .collect_vec();
+    if !missing.is_empty() {
+        miette::bail!(
+            code = "environment-not-locked",
+            help = format!(
+                "the lock-file contains the environments: {}",
+                lock_file.environments().map(|(name, _)| name).format(", ")
+            ),
+            "lock-file '{}' doesn't contain the environments: {}",
+            path.display(),
+            missing.iter().format(", ")
+        );
+    }
+    Ok(lock_file)
+}
+
+/// Returns the locked packages of an environment for a platform, errors if
+/// the environment doesn't support the platform or it is not locked.
+pub(super) fn locked_platform_packages(
+    lock_file: &LockFile,
+    environment: &Environment<'_>,
+    platform: Platform,
+) -> miette::Result<Vec<Package>> {
+    // Fail early with the supported platforms if the requested platform is
+    // not part of the environment
+    environment.validate_platform_support(Some(platform))?;
+
+    let locked_environment = lock_file.environment(environment.name().as_str());
+    if let Some(packages) = locked_environment.and_then(|env| env.packages(platform)) {
+        return Ok(packages.collect());
+    }
+
+    let available = locked_environment
+        .map(|env| env.platforms().sorted_by_key(|p| p.as_str()).collect_vec())
+        .unwrap_or_default();
+    let help = match closest_platform(platform.as_str(), available.iter().copied()) {
+        Some(closest) => format!("did you mean '{}'?", closest),
+        None => "run `pixi install` to update the lock file".to_string(),
+    };
+    Err(miette::miette!(
+        code = "platform-not-locked",
+        help = help,
+        "platform '{}' not found in manifest for environment '{}', available platforms: {}",
+        platform,
+        environment.name(),
+        if available.is_empty() {
+            "none".to_string()
+        } else {
+            available.iter().format(", ").to_string()
+        }
+    ))
+}
+
+/// Removes the conda packages that are also locked in the baseline, compared
+/// by url. PyPI packages are kept as-is.
+fn diff_locked_packages(locked_deps: Vec<Package>, baseline: &[Package]) -> Vec<Package> {
+    let baseline_urls: HashSet<_> = baseline
+        .iter()
+        .filter_map(Package::as_conda)
+        .map(CondaPackage::url)
+        .collect();
+    locked_deps
+        .into_iter()
+        .filter(|p| {
+            p.as_conda()
+                .map_or(true, |p| !baseline_urls.contains(p.url()))
+        })
+        .collect()
+}
+
+/// Partitions the locked packages of a platform into the conda packages to
+/// export and, if `collect_pypi` is set, the pypi packages.
+pub(super) fn partition_locked_packages(
+    options: &ExplicitSpecOptions,
+    collect_pypi: bool,
+    platform: Platform,
+    locked_deps: Vec<Package>,
+) -> miette::Result<(Vec<CondaPackage>, Vec<PypiPackageData>)> {
+    let mut conda_packages_from_lockfile: Vec<CondaPackage> = Vec::new();
+    let mut pypi_packages_from_lockfile: Vec<PypiPackageData> = Vec::new();
+
+    for package in locked_deps {
+        match package {
+            Package::Conda(p) => conda_packages_from_lockfile.push(p),
+            Package::Pypi(pyp) => {
+                if options.ignore_pypi_packages {
+                    tracing::warn!(
+                        "ignoring PyPI package {} since PyPI packages are not supported",
+                        pyp.data().package.name
+                    );
+                } else if collect_pypi {
+                    pypi_packages_from_lockfile.push(pyp.data().package.clone());
+                } else {
+                    return Err(unsupported_pypi_packages());
+                }
+            }
+        }
+    }
+
+    if !options.packages.is_empty() {
+        conda_packages_from_lockfile =
+            filter_conda_packages(conda_packages_from_lockfile, &options.packages, platform)?;
+    }
+    if !options.dependencies_of.is_empty() {
+        conda_packages_from_lockfile = dependency_closure(
+            conda_packages_from_lockfile,
+            &options.dependencies_of,
+            platform,
+        )?;
+    }
+
+    Ok((conda_packages_from_lockfile, pypi_packages_from_lockfile))
+}
+
+/// The arguments of the exports that write the locked conda packages of a
+/// platform as match specs, like `micromamba-spec` and `match-spec`. The PyPI
+/// packages are handled like in the explicit spec.
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct MatchSpecArgs {
+    /// The directory to write the exported files to. Defaults to the current
+    /// working directory.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// The platform to render. Defaults to the only platform of the
+    /// environment in the lock file, or the current platform.
+    #[arg(long)]
+    pub platform: Option<Platform>,
+
+    /// The environment to render. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    /// What to do with the PyPI packages of the environment: fail the export
+    /// (`bail`, the default), skip them (`ignore`) or also write them to a pip
+    /// requirements file (`requirements`).
+    #[arg(long, value_enum, conflicts_with_all = ["write_pypi_requirements", "ignore_pypi_errors"])]
+    pub pypi_mode: Option<PypiMode>,
+
+    /// Also write a pip requirements file with the PyPI dependencies.
+    /// Deprecated, use `--pypi-mode requirements` instead.
+    #[arg(long, conflicts_with = "ignore_pypi_errors")]
+    pub write_pypi_requirements: bool,
+
+    /// PyPI dependencies are not supported in a match spec file. This flag
+    /// allows creating the spec file even if PyPI dependencies are present.
+    /// Deprecated, use `--pypi-mode ignore` instead.
+    #[arg(long)]
+    pub ignore_pypi_errors: bool,
+
+    /// Export from this lock file instead of the lock file of the project.
+    /// The file is used as-is, it is never updated.
+    #[arg(long, value_name = "PATH", conflicts_with = "no_lockfile_update")]
+    pub lockfile: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// Writes the locked conda packages of a platform, sorted by name, to the
+/// `{file_prefix}-{platform}-{environment}.txt` file rendered by `render`.
+/// With `--pypi-mode requirements` the PyPI packages are written to a
+/// requirements file next to it.
+pub(super) async fn export_match_spec_file(
+    project: &Project,
+    args: MatchSpecArgs,
+    file_prefix: &str,
+    kind: &str,
+    render: impl FnOnce(&[CondaPackage]) -> String,
+) -> miette::Result<()> {
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+    let lock_file = match &args.lockfile {
+        Some(path) => load_lock_file_from_path(path, &[environment.clone()])?,
+        None => {
+            super::load_lock_file(
+                project,
+                args.prefix_update_config.lock_file_usage(),
+                args.prefix_update_config.no_install(),
+                0,
+            )
+            .await?
+        }
+    };
+
+    let platform = args
+        .platform
+        .unwrap_or_else(|| super::default_platform(&lock_file, &environment));
+    let locked_deps = locked_platform_packages(&lock_file, &environment, platform)?;
+
+    let pypi_mode = PypiMode::from_args(
+        args.pypi_mode,
+        args.write_pypi_requirements,
+        args.ignore_pypi_errors,
+    );
+    let options = ExplicitSpecOptions {
+        ignore_pypi_packages: pypi_mode == PypiMode::Ignore,
+        ..ExplicitSpecOptions::default()
+    };
+    let (mut conda_packages, pypi_packages) = partition_locked_packages(
+        &options,
+        pypi_mode == PypiMode::Requirements,
+        platform,
+        locked_deps,
+    )?;
+    conda_packages.sort_by(|a, b| {
+        a.package_record()
+            .name
+            .as_normalized()
+            .cmp(b.package_record().name.as_normalized())
+    });
+
+    let output_dir = match args.output_dir {
+        Some(output_dir) => output_dir,
+        None => cwd()?,
+    };
+    let environment_name = environment.name().as_str();
+    let target = output_dir.join(format!(
+        "{}-{}-{}.txt",
+        file_prefix, platform, environment_name
+    ));
+
+    tracing::info!("Creating {} file", kind);
+    fs::write(&target, render(&conda_packages)).map_err(|e| {
+        miette::miette!(
+            "Could not write {} file '{}': {}",
+            kind,
+            target.display(),
+            e
+        )
+    })?;
+
+    if !pypi_packages.is_empty() {
+        let target = output_dir.join(format!(
+            "requirements-{}-{}.txt",
+            platform, environment_name
+        ));
+        let contents = render_pypi_requirements(
+            &target,
+            &pypi_packages,
+            PypiHashAlgorithm::Sha256,
+            project.root(),
+            false,
+            None,
+            &[],
+            &[],
+        )?;
+
+        tracing::info!("Creating pypi requirements file");
+        fs::write(&target, contents).map_err(|e| {
+            miette::miette!(
+                "Could not write requirements file '{}': {}",
+                target.display(),
+                e
+            )
+        })?;
+    }
+
+    Ok(())
+}
+
+/// The error for PyPI packages in an export that only supports conda packages.
+fn unsupported_pypi_packages() -> miette::Report {
+    miette::miette!(
+        code = "pypi-not-supported",
+        "PyPI packages are not supported in a conda explicit spec. \
+        Specify `--pypi-mode ignore` to ignore them, or \
+        `--pypi-mode requirements` to write them to a separate requirements file"
+    )
+}
+
+/// Appends a `# virtual-package: ` comment for every virtual package that the
+/// conda packages depend on to the rendered explicit spec if
+/// `--include-virtual-packages` is given. The comment lists the version of the
+/// virtual package the environment was solved for, and the requirements of
+/// the packages on it.
+fn append_virtual_packages(
+    args: &Args,
+    environment: &Environment<'_>,
+    platform: Platform,
+    mut contents: String,
+    conda_packages: &[CondaPackage],
+) -> miette::Result<String> {
+    if !args.include_virtual_packages {
+        return Ok(contents);
+    }
+
+    let mut requirements: BTreeMap<String, BTreeSet<&str>> = BTreeMap::new();
+    for package in conda_packages {
+        for depend in package
+            .package_record()
+            .depends
+            .iter()
+            .filter(|d| d.starts_with("__"))
+        {
+            let spec = MatchSpec::from_str(depend, ParseStrictness::Lenient).into_diagnostic()?;
+            if let Some(name) = &spec.name {
+                requirements
+                    .entry(name.as_normalized().to_string())
+                    .or_default()
+                    .insert(depend.as_str());
+            }
+        }
+    }
+
+    let virtual_packages = environment
+        .virtual_packages(platform)
+        .into_iter()
+        .map(GenericVirtualPackage::from)
+        .collect_vec();
+    for (name, specs) in requirements {
+        let version = virtual_packages
+            .iter()
+            .find(|vpkg| vpkg.name.as_normalized() == name)
+            .map(|vpkg| format!(" {}", vpkg.version))
+            .unwrap_or_default();
+        contents.push_str(&format!(
+            "# virtual-package: {}{} ({})\n",
+            name,
+            version,
+            specs.iter().format(", ")
+        ));
+    }
+    Ok(contents)
+}
+
+/// The locked packages that may violate strict channel priority.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct ChannelPriorityIssues {
+    /// The packages from a channel with a lower priority than the first
+    /// channel, strict priority would take them from a higher priority
+    /// channel if that has a package with the same name.
+    lower_priority: Vec<String>,
+    /// The packages that are not from any of the channels.
+    unknown_channel: Vec<String>,
+}
+
+/// Matches the urls of the conda packages against the channel urls, that are
+/// ordered from the highest to the lowest priority.
+fn channel_priority_issues(
+    channels: &[String],
+    conda_packages: &[CondaPackage],
+) -> ChannelPriorityIssues {
+    let mut issues = ChannelPriorityIssues::default();
+    for package in conda_packages {
+        let name = package.package_record().name.as_normalized().to_string();
+        match channels
+            .iter()
+            .position(|channel| package.url().as_str().starts_with(channel.as_str()))
+        {
+            Some(0) => {}
+            Some(_) => issues.lower_priority.push(name),
+            None => issues.unknown_channel.push(name),
+        }
+    }
+    issues
+}
+
+/// Warns about the locked packages that may not be locked the same way when
+/// the environment is solved again with strict channel priority, which is the
+/// default. Nothing is checked if channel priority is disabled.
+fn check_channel_priority(
+    environment: &Environment<'_>,
+    platform: Platform,
+    conda_packages: &[CondaPackage],
+) -> miette::Result<()> {
+    let priority = environment.channel_priority().into_diagnostic()?;
+    if priority == Some(ChannelPriority::Disabled) {
+        tracing::debug!(
+            "not checking the channel priority, it is disabled for environment '{}'",
+            environment.name()
+        );
+        return Ok(());
+    }
+
+    let channel_config = environment.project().channel_config();
+    let channels = environment
+        .channels()
+        .into_iter()
+        .map(|channel| channel.clone().into_base_url(&channel_config).to_string())
+        .collect_vec();
+    let issues = channel_priority_issues(&channels, conda_packages);
+
+    if !issues.lower_priority.is_empty() {
+        tracing::warn!(
+            "the following packages for {} are from a lower priority channel than {}, with strict channel priority a re-solve would take them from a higher priority channel that has them: {}",
+            platform,
+            channels[0],
+            issues.lower_priority.iter().format(", ")
+        );
+    }
+    if !issues.unknown_channel.is_empty() {
+        tracing::warn!(
+            "the following packages for {} are not from any channel of environment '{}': {}",
+            platform,
+            environment.name(),
+            issues.unknown_channel.iter().format(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Splits the locked packages of a platform into the conda packages to export
+/// and the pypi packages to write to the requirements file.
+fn split_locked_packages(
+    args: &Args,
+    environment: &Environment<'_>,
+    platform: Platform,
+    locked_deps: Vec<Package>,
+) -> miette::Result<(Vec<CondaPackage>, Vec<PypiPackageData>)> {
+    let (conda_packages, pypi_packages) = partition_locked_packages(
+        &args.spec_options(),
+        args.write_pypi_requirements || args.write_pypi_constraints || args.pypi_inline,
+        platform,
+        locked_deps,
+    )?;
+
+    if args.require_pypi_hashes {
+        ensure_pypi_hashes(&pypi_packages)?;
+    }
+
+    if args.check_channel_priority {
+        check_channel_priority(environment, platform, &conda_packages)?;
+    }
+
+    Ok((conda_packages, pypi_packages))
+}
+
+impl Project {
+    /// Builds the conda explicit spec of an environment for a platform from
+    /// the lock-file of the project. The lock-file is updated first if it is
+    /// out of date and the options allow it.
+    pub async fn export_conda_explicit_spec(
+        &self,
+        environment: &Environment<'_>,
+        platform: Platform,
+        options: ExplicitSpecOptions,
+    ) -> miette::Result<ExplicitEnvironmentSpec> {
+        let (conda_packages, pypi_packages) = self
+            .locked_packages(environment, platform, &options)
+            .await?;
+        if !pypi_packages.is_empty() {
+            return Err(unsupported_pypi_packages());
+        }
+
+        build_explicit_spec(platform, &conda_packages, &options)
+    }
+
+    /// Returns the locked conda and pypi packages of an environment for a
+    /// platform from the lock-file of the project, with the conda packages
+    /// filtered by the options. The lock-file is updated first if it is out
+    /// of date and the options allow it. Errors with the available platforms
+    /// if the platform is not locked for the environment.
+    pub async fn locked_packages(
+        &self,
+        environment: &Environment<'_>,
+        platform: Platform,
+        options: &ExplicitSpecOptions,
+    ) -> miette::Result<(Vec<CondaPackage>, Vec<PypiPackageData>)> {
+        let lock_file = super::load_lock_file(
+            self,
+            options.lock_file_usage,
+            options.no_install,
+            options.max_retries,
+        )
+        .await?;
+        let locked_deps = locked_platform_packages(&lock_file, environment, platform)?;
+        partition_locked_packages(options, true, platform, locked_deps)
+    }
+}
+
+/// Returns whether an explicit spec with `package_count` conda packages should
+/// be written. Conda rejects an explicit spec without packages, so for a pure
+/// PyPI environment it is skipped with a warning if pypi files are written and
+/// an error otherwise.
+fn should_write_explicit_spec(
+    args: &Args,
+    environment_name: &str,
+    platform: impl std::fmt::Display,
+    package_count: usize,
+) -> miette::Result<bool> {
+    if package_count > 0 {
+        return Ok(true);
+    }
+    if args.write_pypi_requirements || args.write_pypi_constraints {
+        tracing::warn!(
+            "skipping the conda lock file, environment '{}' has no conda packages for {}",
+            environment_name,
+            platform
+        );
+        return Ok(false);
+    }
+    miette::bail!(
+        code = "no-conda-packages",
+        "environment '{}' has no conda packages for {}, use `--pypi-mode requirements` to only export its PyPI packages",
+        environment_name,
+        platform
+    );
+}
+
+/// Builds the pypi requirements and constraints files of a platform if
+/// requested.
+fn export_pypi_files(
+    args: &Args,
+    environment: &Environment<'_>,
+    platform: Platform,
+    pypi_packages: &[PypiPackageData],
+    output_dir: &Path,
+) -> miette::Result<Vec<ExportedFile>> {
+    let environment_name = environment.name().as_str();
+    if !args.write_pypi_requirements && !args.write_pypi_constraints {
+        return Ok(Vec::new());
+    }
+    if pypi_packages.is_empty() {
+        log_progress(
+            args,
+            format_args!(
+                "Skipping pypi files, environment '{}' has no PyPI packages for {}",
+                environment_name, platform
+            ),
+        );
+        return Ok(Vec::new());
+    }
+
+    let mut written = Vec::new();
+    if args.write_pypi_requirements {
+        log_progress(args, format_args!("Creating pypi requirements file"));
+        let pypi_target = export_path(
+            args,
+            output_dir,
+            "requirements",
+            platform.as_str(),
+            environment_name,
+            "txt",
+        );
+        if args.include_build_deps {
+            let sources = pypi_packages
+                .iter()
+                .filter(|p| is_source_pypi_package(p))
+                .map(|p| p.name.to_string())
+                .collect_vec();
+            if !sources.is_empty() {
+                tracing::warn!(
+                    "the lock file doesn't record the build requirements of PyPI source distributions, they are not included for: {}",
+                    sources.iter().format(", ")
+                );
+            }
+        }
+        let contents = render_pypi_requirements(
+            &pypi_target,
+            &sorted_pypi_packages(args, pypi_packages, requirement_sort_key),
+            args.pypi_hash_algo,
+            environment.project().root(),
+            args.relative_paths,
+            args.pypi_index_url.as_ref(),
+            &args.pypi_extra_index_url,
+            &args.pip_option,
+        )?;
+        written.push(ExportedFile {
+            path: pypi_target,
+            platform: Some(platform),
+            environment: environment_name.to_string(),
+            packages: pypi_packages.len(),
+            pypi: true,
+            contents,
+            sizes: BTreeMap::new(),
+        });
+    }
+
+    if args.write_pypi_constraints {
+        log_progress(args, format_args!("Creating pypi constraints file"));
+        let constraints_target = export_path(
+            args,
+            output_dir,
+            "constraints",
+            platform.as_str(),
+            environment_name,
+            "txt",
+        );
+        written.push(ExportedFile {
+            path: constraints_target,
+            platform: Some(platform),
+            environment: environment_name.to_string(),
+            packages: pypi_packages
+                .iter()
+                .filter(|p| !is_local_pypi_package(p))
+                .count(),
+            pypi: true,
+            contents: render_pypi_constraints(&sorted_pypi_packages(args, pypi_packages, |p| {
+                canonical_pypi_name(&p.name.to_string())
+            })),
+            sizes: BTreeMap::new(),
+        });
+    }
+
+    Ok(written)
+}
+
+/// Builds the explicit spec (and optionally the pypi requirements) for a
+/// single platform of an environment.
+fn export_platform(
+    args: &Args,
+    environment: &Environment<'_>,
+    platform: Platform,
+    locked_deps: Vec<Package>,
+    output_dir: &Path,
+    generated_at: DateTime<Utc>,
+) -> miette::Result<Vec<ExportedFile>> {
+    let environment_name = environment.name().as_str();
+    let (conda_packages_from_lockfile, pypi_packages_from_lockfile) =
+        split_locked_packages(args, environment, platform, locked_deps)?;
+
+    let exported = |extension, contents| ExportedFile {
+        path: export_path(
+            args,
+            output_dir,
+            "conda",
+            platform.as_str(),
+            environment_name,
+            extension,
+        ),
+        platform: Some(platform),
+        environment: environment_name.to_string(),
+        packages: conda_packages_from_lockfile.len(),
+        pypi: false,
+        contents,
+        sizes: channel_sizes(&conda_packages_from_lockfile),
+    };
+
+    let mut written = Vec::new();
+    match args.format {
+        _ if args.only_pypi => {}
+        ExportFormat::Explicit
+            if !should_write_explicit_spec(
+                args,
+                environment_name,
+                platform,
+                conda_packages_from_lockfile.len(),
+            )? => {}
+        ExportFormat::Explicit if args.split_noarch => {
+            let (noarch, platform_packages): (Vec<_>, Vec<_>) = conda_packages_from_lockfile
+                .iter()
+                .partition(|p| p.package_record().subdir == Platform::NoArch.as_str());
+            let header = explicit_spec_header(args, environment, generated_at);
+
+            for (subdir, packages) in [(platform, platform_packages), (Platform::NoArch, noarch)] {
+                if packages.is_empty() {
+                    tracing::debug!(
+                        "skipping the empty {} explicit spec of environment '{}'",
+                        subdir,
+                        environment_name
+                    );
+                    continue;
+                }
+                let path = export_path(
+                    args,
+                    output_dir,
+                    "conda",
+                    subdir.as_str(),
+                    environment_name,
+                    &args.extension,
+                );
+                let ees =
+                    build_explicit_spec(subdir, packages.iter().copied(), &args.spec_options())?;
+                let contents = annotate_packages(
+                    args,
+                    render_explicit_spec(&ees, &header),
+                    packages.iter().copied(),
+                )?;
+                // The pypi and virtual packages belong to the platform, not the
+                // noarch spec
+                let contents = if subdir == platform {
+                    let contents = append_virtual_packages(
+                        args,
+                        environment,
+                        platform,
+                        contents,
+                        &conda_packages_from_lockfile,
+                    )?;
+                    inline_pypi_requirements(
+                        args,
+                        environment,
+                        &path,
+                        contents,
+                        &pypi_packages_from_lockfile,
+                    )?
+                } else {
+                    contents
+                };
+
+                log_progress(args, format_args!("Creating {} conda lock file", subdir));
+                written.push(ExportedFile {
+                    path,
+                    platform: Some(subdir),
+                    environment: environment_name.to_string(),
+                    packages: packages.len(),
+                    pypi: false,
+                    sizes: channel_sizes(packages.iter().copied()),
+                    contents,
+                });
+            }
+        }
+        ExportFormat::Explicit => {
+            let ees = build_explicit_spec(
+                platform,
+                &conda_packages_from_lockfile,
+                &args.spec_options(),
+            )?;
+
+            let contents = annotate_packages(
+                args,
+                render_explicit_spec(&ees, &explicit_spec_header(args, environment, generated_at)),
+                &conda_packages_from_lockfile,
+            )?;
+            let contents = append_virtual_packages(
+                args,
+                environment,
+                platform,
+                contents,
+                &conda_packages_from_lockfile,
+            )?;
+            let contents = inline_pypi_requirements(
+                args,
+                environment,
+                &export_path(
+                    args,
+                    output_dir,
+                    "conda",
+                    platform.as_str(),
+                    environment_name,
+                    &args.extension,
+                ),
+                contents,
+                &pypi_packages_from_lockfile,
+            )?;
+            if args.stdout {
+                print_export(args, &contents);
+                return Ok(Vec::new());
+            }
+
+            log_progress(args, format_args!("Creating conda lock file"));
+            written.push(exported(&args.extension, contents));
+        }
+        ExportFormat::Json => {
+            let spec = build_json_spec(
+                args.export_name(environment_name),
+                platform,
+                &conda_packages_from_lockfile,
+                args.package_order(),
+                args.include_build_string,
+                &args.channel_override,
+            )?;
+            let contents = serde_json::to_string_pretty(&spec).into_diagnostic()?;
+
+            if args.stdout {
+                print_export(args, &format!("{}\n", contents));
+                return Ok(Vec::new());
+            }
+
+            log_progress(args, format_args!("Creating conda json file"));
+            written.push(exported("json", contents));
+        }
+    }
+
+    written.extend(export_pypi_files(
+        args,
+        environment,
+        platform,
+        &pypi_packages_from_lockfile,
+        output_dir,
+    )?);
+
+    Ok(written)
+}
+
+/// Builds the explicit specs of multiple platforms of an environment as a
+/// single file, the pypi requirements are still built per platform.
+fn export_merged(
+    args: &Args,
+    environment: &Environment<'_>,
+    platform_deps: Vec<(Platform, Vec<Package>)>,
+    output_dir: &Path,
+    generated_at: DateTime<Utc>,
+) -> miette::Result<Vec<ExportedFile>> {
+    let mut platform_packages = Vec::new();
+    let mut written = Vec::new();
+    for (platform, locked_deps) in platform_deps {
+        let (conda_packages, pypi_packages) =
+            split_locked_packages(args, environment, platform, locked_deps)?;
+        written.extend(export_pypi_files(
+            args,
+            environment,
+            platform,
+            &pypi_packages,
+            output_dir,
+        )?);
+        platform_packages.push((platform, conda_packages));
+    }
+
+    let package_count = platform_packages
+        .iter()
+        .map(|(_, packages)| packages.len())
+        .sum();
+    if args.only_pypi
+        || !should_write_explicit_spec(
+            args,
+            environment.name().as_str(),
+            "any platform",
+            package_count,
+        )?
+    {
+        return Ok(written);
+    }
+
+    let specs = build_merged_explicit_specs(&platform_packages, &args.spec_options())?;
+
+    log_progress(args, format_args!("Creating merged conda lock file"));
+    let target = export_path(
+        args,
+        output_dir,
+        "conda",
+        "merged",
+        environment.name().as_str(),
+        &args.extension,
+    );
+    written.insert(
+        0,
+        ExportedFile {
+            path: target,
+            platform: None,
+            environment: environment.name().as_str().to_string(),
+            packages: specs.iter().map(|spec| spec.packages.len()).sum(),
+            pypi: false,
+            sizes: channel_sizes(platform_packages.iter().flat_map(|(_, packages)| packages)),
+            contents: annotate_packages(
+                args,
+                render_explicit_specs(
+                    &specs,
+                    &explicit_spec_header(args, environment, generated_at),
+                ),
+                platform_packages.iter().flat_map(|(_, packages)| packages),
+            )?,
+        },
+    );
+
+    Ok(written)
+}
+
+/// Builds the explicit specs for the requested platforms of a single
+/// environment.
+fn export_environment(
+    args: &Args,
+    environment: &Environment<'_>,
+    lock_file: &LockFile,
+    output_dir: &Path,
+    generated_at: DateTime<Utc>,
+) -> miette::Result<Vec<ExportedFile>> {
+    let environment_name = environment.name().as_str();
+    let locked_deps = |platform: Platform| {
+        lock_file
+            .environment(environment_name)
+            .and_then(|env| env.packages(platform).map(Vec::from_iter))
+    };
+
+    let baseline = args
+        .diff_against
+        .clone()
+        .map(|name| {
+            environment
+                .project()
+                .environment_from_name_or_env_var(Some(name))
+        })
+        .transpose()?;
+    let diff = |platform: Platform, deps: Vec<Package>| match &baseline {
+        Some(baseline) => Ok::<_, miette::Report>(diff_locked_packages(
+            deps,
+            &locked_platform_packages(lock_file, baseline, platform)?,
+        )),
+        None => Ok(deps),
+    };
+
+    let mut written = Vec::new();
+    if args.all_platforms || args.platform_from_lockfile {
+        let platforms = if args.platform_from_lockfile {
+            lock_file
+                .environment(environment_name)
+                .map(|env| env.platforms().collect_vec())
+                .unwrap_or_default()
+        } else {
+            environment.platforms().into_iter().collect_vec()
+        };
+
+        let mut platform_deps = Vec::new();
+        for platform in platforms.into_iter().sorted_by_key(|p| p.as_str()) {
+            let Some(deps) = locked_deps(platform) else {
+                tracing::warn!(
+                    "platform '{}' not found in the lock file for environment '{}', skipping",
+                    platform,
+                    environment_name
+                );
+                continue;
+            };
+            platform_deps.push((platform, diff(platform, deps)?));
+        }
+
+        if platform_deps.is_empty() {
+            miette::bail!(
+                code = "platform-not-locked",
+                "none of the platforms of environment '{}' are present in the lock file",
+                environment_name
+            );
+        }
+
+        if args.merged {
+            written.extend(export_merged(
+                args,
+                environment,
+                platform_deps,
+                output_dir,
+                generated_at,
+            )?);
+        } else {
+            // A failing platform doesn't abort the export of the others unless
+            // `--fail-fast` is set
+            let platform_count = platform_deps.len();
+            let mut failed = Vec::new();
+            for (platform, deps) in platform_deps {
+                match export_platform(args, environment, platform, deps, output_dir, generated_at) {
+                    Ok(files) => written.extend(files),
+                    Err(err) if !args.fail_fast => {
+                        tracing::warn!(
+                            "exporting platform '{}' of environment '{}' failed: {}",
+                            platform,
+                            environment_name,
+                            err
+                        );
+                        failed.push((platform, err));
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+
+            if failed.len() == platform_count {
+                miette::bail!(
+                    code = "all-platforms-failed",
+                    "exporting environment '{}' failed for all platforms: {}",
+                    environment_name,
+                    failed
+                        .iter()
+                        .map(|(platform, err)| format!("{}: {}", platform, err))
+                        .format("; ")
+                );
+            }
+            if !failed.is_empty() {
+                tracing::warn!(
+                    "skipped the following platforms of environment '{}' that failed to export: {}",
+                    environment_name,
+                    failed.iter().map(|(platform, _)| platform).format(", ")
+                );
+            }
+        }
+    } else {
+        // Get the platform to render the spec for
+        let platform = args
+            .platform()?
+            .unwrap_or_else(|| super::default_platform(lock_file, environment));
+        let deps = diff(
+            platform,
+            locked_platform_packages(lock_file, environment, platform)?,
+        )?;
+        written.extend(export_platform(
+            args,
+            environment,
+            platform,
+            deps,
+            output_dir,
+            generated_at,
+        )?);
+    }
+
+    Ok(written)
+}
+
+/// An export error as reported with `--error-format json`.
+#[derive(Debug, Serialize)]
+struct JsonError {
+    /// The stable code of the error, `export-failed` for errors without one.
+    code: String,
+    message: String,
+    /// The causes of the error, outermost first.
+    context: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    help: Option<String>,
+}
+
+impl JsonError {
+    fn from_report(err: &miette::Report) -> Self {
+        Self {
+            code: err
+                .code()
+                .map(|code| code.to_string())
+                .unwrap_or_else(|| "export-failed".to_string()),
+            message: err.to_string(),
+            context: err.chain().skip(1).map(ToString::to_string).collect(),
+            help: err.help().map(|help| help.to_string()),
+        }
+    }
+}
+
+/// The exit code of an export that failed because of PyPI packages.
+pub const EXIT_PYPI_NOT_SUPPORTED: i32 = 2;
+/// The exit code of an export of a platform that is not supported or locked.
+pub const EXIT_PLATFORM_NOT_FOUND: i32 = 3;
+/// The exit code of an export of an environment that doesn't exist or is not
+/// locked.
+pub const EXIT_ENVIRONMENT_NOT_FOUND: i32 = 4;
+
+/// Returns the process exit code for a failed export, based on the code of
+/// the error. Errors that have no distinct exit code exit with 1.
+pub fn exit_code(err: &miette::Report) -> i32 {
+    match err.code().map(|code| code.to_string()).as_deref() {
+        Some("pypi-not-supported") => EXIT_PYPI_NOT_SUPPORTED,
+        Some("platform-not-locked" | "unsupported-platform") => EXIT_PLATFORM_NOT_FOUND,
+        Some("unknown-environment" | "environment-not-locked") => EXIT_ENVIRONMENT_NOT_FOUND,
+        _ => 1,
+    }
+}
+
+/// Runs the export and returns the paths of the written files, in the order
+/// they were exported. Nothing is written with `--stdout` or `--dry-run`.
+/// A failure is returned as an [`ExitCodeError`] with the [`exit_code`] of
+/// the error. With `--error-format json` the error is reported as a
+/// [`JsonError`] on stderr right away, so the returned error has no report.
+pub async fn execute(project: Project, args: Args) -> miette::Result<Vec<PathBuf>> {
+    let error_format = args.error_format;
+    export(project, args).await.map_err(|err| {
+        let code = exit_code(&err);
+        let report = match error_format {
+            ErrorFormat::Human => Some(err),
+            ErrorFormat::Json => {
+                match serde_json::to_string(&JsonError::from_report(&err)) {
+                    Ok(error) => eprintln!("{}", error),
+                    Err(e) => return miette::miette!("Could not serialize the error: {}", e),
+                }
+                None
+            }
+        };
+        miette::Report::new(ExitCodeError { code, report })
+    })
+}
+
+async fn export(project: Project, args: Args) -> miette::Result<Vec<PathBuf>> {
+    let mut args = args.with_export_config(super::load_export_config(&project)?);
+    if !args.no_channel_alias {
+        // The overrides on the command line take precedence
+        let mirrors = mirror_channel_overrides(project.config().mirror_map());
+        args.channel_override.extend(mirrors);
+    }
+    args.check_platforms()?;
+    args.apply_pypi_mode()?;
+
+    let environments = if args.all_environments {
+        project.environments()
+    } else {
+        vec![project.environment_from_name_or_env_var(args.environment.clone())?]
+    };
+
+    if args.merged && args.format != ExportFormat::Explicit {
+        miette::bail!(
+            code = "unsupported-format",
+            "`--merged` is only supported for the explicit format"
+        );
+    }
+
+    if args.timestamp && args.no_header {
+        tracing::warn!("ignoring `--timestamp` since `--no-header` is set");
+    }
+
+    if args.include_virtual_packages && args.format != ExportFormat::Explicit {
+        miette::bail!(
+            code = "unsupported-format",
+            "`--include-virtual-packages` is only supported for the explicit format"
+        );
+    }
+
+    if args.append && args.format != ExportFormat::Explicit {
+        miette::bail!(
+            code = "unsupported-format",
+            "`--append` is only supported for the explicit format"
+        );
+    }
+
+    if args.append && args.package_order().sort_by == Some(SortKey::Size) {
+        miette::bail!(
+            code = "conflicting-arguments",
+            help = "sort by `name` or `url`, or export without `--append`",
+            "`--sort-by size` cannot be combined with `--append` since the sizes of the existing packages are unknown"
+        );
+    }
+
+    if args.md5_only && args.format != ExportFormat::Explicit {
+        miette::bail!(
+            code = "unsupported-format",
+            "`--md5-only` is only supported for the explicit format"
+        );
+    }
+
+    if args.verify_urls && args.format != ExportFormat::Explicit {
+        miette::bail!(
+            code = "unsupported-format",
+            "`--verify-urls` is only supported for the explicit format"
+        );
+    }
+
+    if args.split_noarch && args.format != ExportFormat::Explicit {
+        miette::bail!(
+            code = "unsupported-format",
+            "`--split-noarch` is only supported for the explicit format"
+        );
+    }
+
+    if args.pypi_inline && args.format != ExportFormat::Explicit {
+        miette::bail!(
+            code = "unsupported-format",
+            "`--pypi-inline` is only supported for the explicit format"
+        );
+    }
+
+    if args.annotate_packages && args.format != ExportFormat::Explicit {
+        miette::bail!(
+            code = "unsupported-format",
+            "`--annotate-packages` is only supported for the explicit format"
+        );
+    }
+
+    if args.require_pypi_hashes && args.pypi_hash_algo == PypiHashAlgorithm::None {
+        miette::bail!(
+            code = "conflicting-arguments",
+            "`--require-pypi-hashes` cannot be combined with `--pypi-hash-algo none`"
+        );
+    }
+
+    // The explicit spec and the pypi files only differ in extension
+    if let Some(template) = &args.filename_template {
+        if (args.write_pypi_requirements || args.write_pypi_constraints)
+            && !template.contains("{extension}")
+        {
+            miette::bail!(
+                code = "invalid-filename-template",
+                "the filename template must contain `{{extension}}` when pypi files are written"
+            );
+        }
+        if (args.write_pypi_requirements || args.write_pypi_constraints) && args.extension == "txt"
+        {
+            miette::bail!(
+                code = "invalid-filename-template",
+                "the explicit spec and the pypi files can't be told apart with `--filename-template` and `--extension txt`"
+            );
+        }
+        if args.write_pypi_requirements && args.write_pypi_constraints {
+            miette::bail!(
+                code = "invalid-filename-template",
+                "the pypi requirements and constraints files can't be told apart with `--filename-template`"
+            );
+        }
+    }
+
+    // Solving is the expensive part, so the lock file is loaded once for all
+    // the exported environments and platforms
+    let lock_file = match &args.lockfile {
+        Some(path) => load_lock_file_from_path(path, &environments)?,
+        None => {
+            super::load_lock_file(
+                &project,
+                args.lock_file_usage(),
+                args.prefix_update_config.no_install(),
+                args.max_retries,
+            )
+            .await?
+        }
+    };
+
+    let output_dir = match &args.output_dir {
+        Some(output_dir) => output_dir.clone(),
+        None => cwd()?,
+    };
+
+    let generated_at = export_time(std::env::var("SOURCE_DATE_EPOCH").ok().as_deref());
+    let mut written = Vec::new();
+    for environment in &environments {
+        written.extend(export_environment(
+            &args,
+            environment,
+            &lock_file,
+            &output_dir,
+            generated_at,
+        )?);
+    }
+    let mut written = dedup_exported_files(written)?;
+    if args.append {
+        for file in written.iter_mut().filter(|file| !file.pypi) {
+            append_existing_spec(file, args.package_order())?;
+        }
+    }
+    let written = written
+        .into_iter()
+        .map(|mut file| {
+            file.contents = args.newline.normalize(&file.contents);
+            file
+        })
+        .collect_vec();
+
+    if args.verify_urls {
+        verify_package_urls(
+            project.authenticated_client(),
+            &written,
+            Duration::from_secs(args.verify_timeout),
+            args.show_progress(),
+        )
+        .await?;
+    }
+
+    if args.dry_run {
+        if args.print_sizes {
+            log_size_summary(&written);
+        }
+        for file in &written {
+            println!(
+                "Would write {} ({} packages)",
+                file.path.display(),
+                file.packages
+            );
+        }
+        return Ok(Vec::new());
+    }
+
+    // Check all files before writing any of them so nothing is clobbered
+    if args.no_clobber {
+        let existing = written
+            .iter()
+            .filter(|file| file.path.exists())
+            .map(|file| file.path.display())
+            .collect_vec();
+        if !existing.is_empty() {
+            miette::bail!(
+                code = "file-exists",
+                "the following files already exist: {}. Remove them or don't pass `--no-clobber` to overwrite them",
+                existing.iter().format(", ")
+            );
+        }
+    }
+
+    // The `--conda-output` and `--pypi-output` paths can be outside of the
+    // output directory, so the directory of every file is created
+    let directories: BTreeSet<_> = written
+        .iter()
+        .filter_map(|file| file.path.parent())
+        .collect();
+    for directory in directories {
+        fs::create_dir_all(directory).map_err(|e| {
+            miette::miette!(
+                "Could not create output directory '{}': {}",
+                directory.display(),
+                e
+            )
+        })?;
+    }
+    write_export_files(&written).await?;
+
+    if args.print_checksum {
+        for file in &written {
+            println!("{}", checksum_line(&file.path)?);
+        }
+    }
+
+    // The report is written last so it only lists complete exports
+    if let Some(report) = &args.report {
+        write_report(report, &written)?;
+    }
+
+    if let Some(command) = &args.post_export_command {
+        for file in &written {
+            run_post_export_command(command, file)?;
+        }
+    }
+
+    if !args.stdout {
+        log_export_summary(&args, &written);
+    }
+
+    if args.print_sizes {
+        log_size_summary(&written);
+    }
+
+    Ok(written.into_iter().map(|file| file.path).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::project::export::test_utils::{
+        conda_package_with_hashes, conda_package_with_url, pypi_package_with_hashes,
+        pypi_package_with_url, MD5, SHA256, SIX_REQUIREMENT,
+    };
+    use rattler_digest::{parse_digest_from_hex, Md5};
+    use tempfile::tempdir;
+
+    fn test_project_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/cli/project/export/test-data/testenv")
+    }
+
+    fn test_project() -> Project {
+        Project::from_path(&test_project_dir().join("pixi.toml")).unwrap()
+    }
+
+    fn test_lock_file() -> LockFile {
+        LockFile::from_path(&test_project_dir().join("pixi.lock")).unwrap()
+    }
+
+    fn test_args<'a>(extra: impl IntoIterator<Item = &'a str>) -> Args {
+        Args::parse_from(
+            ["conda-explicit-spec", "--no-lockfile-update"]
+                .into_iter()
+                .chain(extra),
+        )
+    }
+
+    fn conda_packages(lock_file: &LockFile, env: &str, platform: Platform) -> Vec<CondaPackage> {
+        lock_file
+            .environment(env)
+            .unwrap()
+            .packages(platform)
+            .unwrap()
+            .filter_map(|p| p.as_conda().cloned())
+            .collect()
+    }
+
+    #[test]
+    fn test_render_explicit_spec() {
+        let lock_file = test_lock_file();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let ees = build_explicit_spec(
+            Platform::Linux64,
+            &packages,
+            &ExplicitSpecOptions::default(),
+        )
+        .unwrap();
+
+        let rendered = render_explicit_spec(&ees, GENERATED_BY_HEADER);
+        assert!(rendered.starts_with("# Generated by `pixi project export`\n"));
+        assert!(rendered.contains("@EXPLICIT"));
+        assert!(rendered.contains(
+            "https://conda.anaconda.org/conda-forge/linux-64/bzip2-1.0.8-h4bc722e_7.conda#62ee74e96c5ebb0af99386de58cf9553"
+        ));
+        assert_eq!(ees.packages.len(), 4);
+
+        let rendered = render_explicit_spec(&ees, "");
+        assert!(rendered.starts_with(ees.to_spec_string().as_str()));
+        assert!(!rendered.contains("Generated by"));
+    }
+
+    #[test]
+    fn test_channel_priority_issues() {
+        let lock_file = test_lock_file();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let names = packages
+            .iter()
+            .map(|p| p.package_record().name.as_normalized().to_string())
+            .collect_vec();
+        let issues = |channels: &[&str]| {
+            let channels = channels.iter().map(|c| c.to_string()).collect_vec();
+            channel_priority_issues(&channels, &packages)
+        };
+
+        assert_eq!(
+            issues(&["https://conda.anaconda.org/conda-forge/"]),
+            ChannelPriorityIssues::default()
+        );
+        assert_eq!(
+            issues(&[
+                "https://conda.anaconda.org/bioconda/",
+                "https://conda.anaconda.org/conda-forge/"
+            ]),
+            ChannelPriorityIssues {
+                lower_priority: names.clone(),
+                unknown_channel: vec![],
+            }
+        );
+        assert_eq!(
+            issues(&["https://prefix.dev/conda-forge/"]),
+            ChannelPriorityIssues {
+                lower_priority: vec![],
+                unknown_channel: names,
+            }
+        );
+
+        // The channels of the test project match the lock file
+        let project = test_project();
+        check_channel_priority(&project.default_environment(), Platform::Linux64, &packages)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_append_virtual_packages() {
+        let project = test_project();
+        let environment = project.default_environment();
+        let lock_file = test_lock_file();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let append = |platform| {
+            let args = test_args(["--include-virtual-packages"]);
+            append_virtual_packages(
+                &args,
+                &environment,
+                platform,
+                "@EXPLICIT\n".to_string(),
+                &packages,
+            )
+            .unwrap()
+        };
+
+        assert_eq!(
+            append(Platform::Linux64),
+            "@EXPLICIT\n# virtual-package: __glibc 2.28 (__glibc >=2.17,<3.0.a0)\n"
+        );
+        // The version is unknown if the environment doesn't have the virtual
+        // package for the platform
+        assert_eq!(
+            append(Platform::OsxArm64),
+            "@EXPLICIT\n# virtual-package: __glibc (__glibc >=2.17,<3.0.a0)\n"
+        );
+
+        let args = test_args([]);
+        assert_eq!(
+            append_virtual_packages(
+                &args,
+                &environment,
+                Platform::Linux64,
+                "@EXPLICIT\n".to_string(),
+                &packages
+            )
+            .unwrap(),
+            "@EXPLICIT\n"
+        );
+    }
+
+    #[test]
+    fn test_size_summary() {
+        let lock_file = test_lock_file();
+        let mut packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let total: u64 = packages
+            .iter()
+            .filter_map(|p| p.package_record().size)
+            .sum();
+        packages.push(conda_package_with_url(
+            "https://mirror.internal/bioconda/linux-64/foo-1.0-h123_0.conda",
+            Some(MD5),
+            None,
+        ));
+
+        let sizes = channel_sizes(&packages);
+        assert_eq!(
+            sizes.keys().collect_vec(),
+            [
+                "https://conda.anaconda.org/conda-forge",
+                "https://mirror.internal/bioconda"
+            ]
+        );
+        assert_eq!(
+            sizes["https://conda.anaconda.org/conda-forge"],
+            ChannelSize {
+                packages: 4,
+                size: total,
+                unknown: 0
+            }
+        );
+        assert_eq!(sizes["https://mirror.internal/bioconda"].unknown, 1);
+
+        let file = ExportedFile {
+            path: PathBuf::from("conda-linux-64-default.lock"),
+            platform: Some(Platform::Linux64),
+            environment: "default".to_string(),
+            packages: packages.len(),
+            pypi: false,
+            contents: String::new(),
+            sizes,
+        };
+        let summary = size_summary(&file);
+        assert_eq!(
+            summary.lines().next().unwrap(),
+            format!(
+                "Download size of conda-linux-64-default.lock: {} (5 packages, 1 without a recorded size)",
+                indicatif::HumanBytes(total)
+            )
+        );
+        assert!(
+            summary.contains(&format!(
+                "\n  https://conda.anaconda.org/conda-forge: {} (4 packages)",
+                indicatif::HumanBytes(total)
+            )),
+            "{summary}"
+        );
+        assert!(summary.ends_with(
+            "\n  https://mirror.internal/bioconda: 0 B (1 packages, 1 without a recorded size)"
+        ));
+    }
+
+    #[test]
+    fn test_group_by_channel() {
+        let contents = "# Generated by `pixi project export`\n\
+            @EXPLICIT\n\
+            # subdir: linux-64\n\
+            https://conda.anaconda.org/conda-forge/linux-64/a-1.0-0.conda#1\n\
+            https://mirror.internal/bioconda/linux-64/b-1.0-0.conda#2\n\
+            https://conda.anaconda.org/conda-forge/noarch/c-1.0-0.conda#3\n\
+            # pypi: six==1.16.0\n";
+        assert_eq!(
+            group_by_channel(contents),
+            "# Generated by `pixi project export`\n\
+            @EXPLICIT\n\
+            # --- channel: https://conda.anaconda.org/conda-forge ---\n\
+            # subdir: linux-64\n\
+            https://conda.anaconda.org/conda-forge/linux-64/a-1.0-0.conda#1\n\
+            https://conda.anaconda.org/conda-forge/noarch/c-1.0-0.conda#3\n\
+            # --- channel: https://mirror.internal/bioconda ---\n\
+            https://mirror.internal/bioconda/linux-64/b-1.0-0.conda#2\n\
+            # pypi: six==1.16.0\n"
+        );
+
+        // The entries of every spec of a merged export are grouped separately
+        let merged = "@EXPLICIT\nhttps://x.org/c/noarch/a-1-0.conda\n\n\
+            @EXPLICIT\nhttps://x.org/c/linux-64/b-1-0.conda\n";
+        assert_eq!(
+            group_by_channel(merged),
+            "@EXPLICIT\n# --- channel: https://x.org/c ---\nhttps://x.org/c/noarch/a-1-0.conda\n\n\
+            @EXPLICIT\n# --- channel: https://x.org/c ---\nhttps://x.org/c/linux-64/b-1-0.conda\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_annotate() {
+        let project = test_project();
+        let environment = project.default_environment();
+        let generated_at = export_time(Some("1700000000"));
+        let header = |args: Args| explicit_spec_header(&args, &environment, generated_at);
+
+        assert_eq!(
+            header(test_args(["--annotate"])),
+            format!(
+                "# Generated by `pixi project export`\n\
+                # environment: default\n\
+                # pixi-version: {}\n\
+                # channel: https://conda.anaconda.org/conda-forge/\n",
+                consts::PIXI_VERSION
+            )
+        );
+        assert_eq!(
+            header(test_args(["--annotate", "--no-header"])),
+            "# channel: https://conda.anaconda.org/conda-forge/\n"
+        );
+        assert_eq!(
+            header(test_args([])),
+            format!(
+                "{}# environment: default\n# pixi-version: {}\n",
+                GENERATED_BY_HEADER,
+                consts::PIXI_VERSION
+            )
+        );
+
+        // The timestamp is the last header line, and only written with the
+        // header
+        assert_eq!(
+            header(test_args(["--timestamp"])).lines().last(),
+            Some("# generated-at: 2023-11-14T22:13:20Z")
+        );
+        assert!(header(test_args(["--timestamp", "--no-header"])).is_empty());
+
+        // The annotations are comments so the spec can still be parsed
+        let temp_dir = tempdir().unwrap();
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--annotate",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+        let target = temp_dir.path().join("conda-linux-64-default.lock");
+        let contents = fs::read_to_string(&target).unwrap();
+        assert!(contents.starts_with(&header(test_args(["--annotate"]))));
+        assert!(
+            contents.contains(
+                "# subdir: noarch\nhttps://conda.anaconda.org/conda-forge/noarch/tzdata-2024a-h0c530f3_0.conda#"
+            ),
+            "{contents}"
+        );
+        assert_eq!(contents.matches("# subdir: linux-64\n").count(), 3);
+        assert_eq!(
+            ExplicitEnvironmentSpec::from_path(&target)
+                .unwrap()
+                .packages
+                .len(),
+            4
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_to_output_dir() {
+        let temp_dir = tempdir().unwrap();
+        let output_dir = temp_dir.path().join("dist").join("specs");
+
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        let spec = fs::read_to_string(output_dir.join("conda-linux-64-default.lock")).unwrap();
+        assert!(spec.contains("@EXPLICIT"));
+    }
+
+    #[tokio::test]
+    async fn test_export_all_platforms() {
+        let temp_dir = tempdir().unwrap();
+
+        let args = test_args([
+            "--all-platforms",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        let written = execute(test_project(), args).await.unwrap();
+
+        let expected = [
+            "conda-linux-64-default.lock",
+            "conda-osx-arm64-default.lock",
+        ]
+        .map(|name| temp_dir.path().join(name));
+        assert_eq!(
+            written.iter().sorted().collect_vec(),
+            expected.iter().collect_vec()
+        );
+        assert!(expected.iter().all(|path| path.is_file()));
+    }
+
+    #[test]
+    fn test_explicit_spec_hash_fragment() {
+        let fragment = |md5, sha256| {
+            let package = conda_package_with_hashes(md5, sha256);
+            build_explicit_spec(
+                Platform::Linux64,
+                [&package],
+                &ExplicitSpecOptions::default(),
+            )
+            .map(|ees| {
+                ees.packages[0]
+                    .url
+                    .fragment()
+                    .map(ToOwned::to_owned)
+                    .unwrap()
+            })
+        };
+
+        assert_eq!(fragment(Some(MD5), None).unwrap(), MD5);
+        assert_eq!(
+            fragment(None, Some(SHA256)).unwrap(),
+            format!("sha256:{SHA256}")
+        );
+        assert_eq!(fragment(Some(MD5), Some(SHA256)).unwrap(), MD5);
+        assert!(fragment(None, None).is_err());
+    }
+
+    #[test]
+    fn test_explicit_spec_strip_query() {
+        let url = "https://mirror.internal/conda-forge/linux-64/foo-1.0-h123_0.conda?token=secret";
+        let package = conda_package_with_url(url, Some(MD5), None);
+        let spec_url = |strip_query| {
+            let options = ExplicitSpecOptions {
+                strip_query,
+                ..ExplicitSpecOptions::default()
+            };
+            build_explicit_spec(Platform::Linux64, [&package], &options)
+                .unwrap()
+                .packages[0]
+                .url
+                .to_string()
+        };
+
+        assert_eq!(spec_url(false), format!("{url}#{MD5}"));
+        assert_eq!(
+            spec_url(true),
+            format!("https://mirror.internal/conda-forge/linux-64/foo-1.0-h123_0.conda#{MD5}")
+        );
+    }
+
+    #[test]
+    fn test_mirror_channel_overrides() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        let mirrors = HashMap::from([
+            (
+                url("https://conda.anaconda.org"),
+                vec![url("https://mirror.internal/conda")],
+            ),
+            (
+                url("https://conda.anaconda.org/conda-forge/"),
+                vec![
+                    url("https://prefix.dev/conda-forge/"),
+                    url("https://mirror.internal/conda-forge/"),
+                ],
+            ),
+            (
+                url("https://conda.anaconda.org/bioconda/"),
+                vec![url("oci://ghcr.io/channel-mirrors/bioconda")],
+            ),
+        ]);
+
+        let overrides = mirror_channel_overrides(&mirrors);
+        assert_eq!(
+            overrides,
+            [
+                ChannelOverride {
+                    from: "https://conda.anaconda.org/conda-forge/".to_string(),
+                    to: "https://prefix.dev/conda-forge/".to_string(),
+                },
+                ChannelOverride {
+                    from: "https://conda.anaconda.org/".to_string(),
+                    to: "https://mirror.internal/conda/".to_string(),
+                },
+            ]
+        );
+
+        let package = conda_package_with_hashes(Some(MD5), None);
+        assert_eq!(
+            override_channel(package.url(), &overrides)
+                .unwrap()
+                .as_str(),
+            "https://prefix.dev/conda-forge/linux-64/foo-1.0-h123_0.conda"
+        );
+    }
+
+    #[test]
+    fn test_check_spec_platform() {
+        let lock_file = test_lock_file();
+        let linux = conda_packages(&lock_file, "default", Platform::Linux64);
+        let noarch = linux
+            .iter()
+            .filter(|p| p.package_record().subdir == Platform::NoArch.as_str())
+            .collect_vec();
+        assert!(!noarch.is_empty());
+
+        // A noarch-only spec matches any platform
+        for platform in [Platform::Linux64, Platform::OsxArm64, Platform::NoArch] {
+            check_spec_platform(platform, noarch.iter().copied(), true).unwrap();
+        }
+        check_spec_platform(Platform::Linux64, &linux, true).unwrap();
+
+        // Packages of another subdir are only an error with `--strict-platform`
+        let osx = conda_packages(&lock_file, "default", Platform::OsxArm64);
+        check_spec_platform(Platform::Linux64, &osx, false).unwrap();
+        let err = check_spec_platform(Platform::Linux64, &osx, true).unwrap_err();
+        assert_eq!(
+            err.code().map(|code| code.to_string()).as_deref(),
+            Some("platform-mismatch")
+        );
+        assert!(err.to_string().contains("(osx-arm64)"), "{err}");
+
+        let options = ExplicitSpecOptions {
+            strict_platform: true,
+            ..ExplicitSpecOptions::default()
+        };
+        assert!(build_explicit_spec(Platform::Linux64, &osx, &options).is_err());
+    }
+
+    #[test]
+    fn test_explicit_spec_dedup() {
+        let foo = conda_package_with_hashes(Some(MD5), None);
+        let url = "https://conda.anaconda.org/conda-forge/linux-64/bar-1.0-h123_0.conda";
+        let bar = conda_package_with_url(url, Some(MD5), None);
+        let packages = [&foo, &bar, &foo];
+        let spec_urls = |no_dedup| {
+            let options = ExplicitSpecOptions {
+                no_dedup,
+                ..ExplicitSpecOptions::default()
+            };
+            build_explicit_spec(Platform::Linux64, packages, &options)
+                .unwrap()
+                .packages
+                .into_iter()
+                .map(|entry| entry.url.to_string())
+                .collect_vec()
+        };
+
+        let foo_url =
+            format!("https://conda.anaconda.org/conda-forge/linux-64/foo-1.0-h123_0.conda#{MD5}");
+        let bar_url = format!("{url}#{MD5}");
+        assert_eq!(spec_urls(false), [bar_url.clone(), foo_url.clone()]);
+        assert_eq!(spec_urls(true), [bar_url, foo_url.clone(), foo_url]);
+    }
+
+    #[test]
+    fn test_explicit_spec_md5_only() {
+        let md5_only = ExplicitSpecOptions {
+            md5_only: true,
+            ..ExplicitSpecOptions::default()
+        };
+        let spec_url = |package: &CondaPackage| {
+            build_explicit_spec(Platform::Linux64, [package], &md5_only)
+                .map(|ees| ees.packages[0].url.to_string())
+        };
+
+        let both = conda_package_with_hashes(Some(MD5), Some(SHA256));
+        assert!(spec_url(&both).unwrap().ends_with(&format!("#{MD5}")));
+
+        // A sha256 fragment on the url is replaced by the md5 hash
+        let url = format!(
+            "https://conda.anaconda.org/conda-forge/linux-64/foo-1.0-h123_0.conda#sha256:{SHA256}"
+        );
+        let with_fragment = conda_package_with_url(&url, Some(MD5), Some(SHA256));
+        assert!(spec_url(&with_fragment)
+            .unwrap()
+            .ends_with(&format!("#{MD5}")));
+
+        // There is no fallback to the sha256 hash
+        let sha256_only = conda_package_with_hashes(None, Some(SHA256));
+        let err = spec_url(&sha256_only).unwrap_err();
+        assert_eq!(err.to_string(), "Package foo does not contain an md5 hash");
+    }
+
+    #[test]
+    fn test_pypi_hash_algorithm() {
+        let md5 = parse_digest_from_hex::<Md5>(MD5).unwrap();
+        let sha256 = parse_digest_from_hex::<Sha256>(SHA256).unwrap();
+
+        let both = pypi_package_with_hashes(Some(PackageHashes::Md5Sha256(md5, sha256)));
+        assert_eq!(
+            get_pypi_hash_str(&both, PypiHashAlgorithm::Sha256).unwrap(),
+            format!("--hash=sha256:{SHA256}")
+        );
+        assert_eq!(
+            get_pypi_hash_str(&both, PypiHashAlgorithm::Md5).unwrap(),
+            format!("--hash=md5:{MD5}")
+        );
+        assert_eq!(get_pypi_hash_str(&both, PypiHashAlgorithm::None), None);
+
+        // Falls back to the available hash
+        let sha256_only = pypi_package_with_hashes(Some(PackageHashes::Sha256(sha256)));
+        assert_eq!(
+            get_pypi_hash_str(&sha256_only, PypiHashAlgorithm::Md5).unwrap(),
+            format!("--hash=sha256:{SHA256}")
+        );
+        let md5_only = pypi_package_with_hashes(Some(PackageHashes::Md5(md5)));
+        assert_eq!(
+            get_pypi_hash_str(&md5_only, PypiHashAlgorithm::Sha256).unwrap(),
+            format!("--hash=md5:{MD5}")
+        );
+
+        let no_hash = pypi_package_with_hashes(None);
+        assert_eq!(get_pypi_hash_str(&no_hash, PypiHashAlgorithm::Sha256), None);
+    }
+
+    #[tokio::test]
+    async fn test_export_pypi_requirements() {
+        let temp_dir = tempdir().unwrap();
+        let output_dir = temp_dir.path().to_str().unwrap();
+
+        for environment in ["default", "with-pypi"] {
+            let args = test_args([
+                "--platform",
+                "linux-64",
+                "--environment",
+                environment,
+                "--write-pypi-requirements",
+                "--output-dir",
+                output_dir,
+            ]);
+            execute(test_project(), args).await.unwrap();
+        }
+
+        // Only environments that contain pypi packages get a requirements file
+        assert!(temp_dir
+            .path()
+            .join("conda-linux-64-default.lock")
+            .is_file());
+        assert!(!temp_dir
+            .path()
+            .join("requirements-linux-64-default.txt")
+            .exists());
+
+        let requirements =
+            fs::read_to_string(temp_dir.path().join("requirements-linux-64-with-pypi.txt"))
+                .unwrap();
+        assert_eq!(requirements, SIX_REQUIREMENT);
+    }
+
+    #[tokio::test]
+    async fn test_export_all_environments() {
+        let temp_dir = tempdir().unwrap();
+
+        let args = test_args([
+            "--all-environments",
+            "--platform",
+            "osx-arm64",
+            "--ignore-pypi-errors",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        assert!(temp_dir
+            .path()
+            .join("conda-osx-arm64-default.lock")
+            .is_file());
+        assert!(temp_dir
+            .path()
+            .join("conda-osx-arm64-with-pypi.lock")
+            .is_file());
+    }
+
+    #[test]
+    fn test_ensure_valid_hash_fragments() {
+        assert!(is_valid_hash_fragment(MD5));
+        assert!(is_valid_hash_fragment(&format!("md5:{MD5}")));
+        assert!(is_valid_hash_fragment(&format!("sha256:{SHA256}")));
+        assert!(!is_valid_hash_fragment(SHA256));
+        assert!(!is_valid_hash_fragment(&format!("sha256:{MD5}")));
+        assert!(!is_valid_hash_fragment(&format!("sha1:{MD5}")));
+        assert!(!is_valid_hash_fragment(&"z".repeat(32)));
+
+        let path = Path::new("conda-linux-64-default.lock");
+        let url = "https://conda.anaconda.org/conda-forge/linux-64/foo-1.0-h123_0.conda";
+        let spec = |fragment: &str| format!("# platform: linux-64\n@EXPLICIT\n{url}{fragment}\n");
+        assert!(ensure_valid_hash_fragments(path, &spec("")).is_ok());
+        assert!(ensure_valid_hash_fragments(path, &spec(&format!("#{MD5}"))).is_ok());
+        let err = ensure_valid_hash_fragments(path, &spec("#1234")).unwrap_err();
+        assert_eq!(err.code().unwrap().to_string(), "invalid-hash");
+        assert!(err.to_string().contains(&format!("{url}#1234")), "{err}");
+    }
+
+    #[test]
+    fn test_explicit_spec_existing_fragment() {
+        let base_url = "https://conda.anaconda.org/conda-forge/linux-64/foo-1.0-h123_0.conda";
+        let fragment = |url: &str| {
+            let package = conda_package_with_url(url, Some(MD5), Some(SHA256));
+            let ees = build_explicit_spec(
+                Platform::Linux64,
+                [&package],
+                &ExplicitSpecOptions::default(),
+            )
+            .unwrap();
+            ees.packages[0].url.fragment().unwrap().to_owned()
+        };
+
+        // A fragment that matches one of the hashes is preserved
+        assert_eq!(
+            fragment(&format!("{base_url}#sha256:{SHA256}")),
+            format!("sha256:{SHA256}")
+        );
+        assert_eq!(fragment(&format!("{base_url}#{MD5}")), MD5);
+
+        // A conflicting fragment is replaced by the hash of the record
+        assert_eq!(fragment(&format!("{base_url}#sha256:deadbeef")), MD5);
+    }
+
+    #[tokio::test]
+    async fn test_export_unsupported_platform() {
+        let temp_dir = tempdir().unwrap();
+
+        let args = test_args([
+            "--platform",
+            "win-64",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        let err = execute(test_project(), args).await.unwrap_err();
+
+        assert_eq!(err.to_string(), "the project does not support 'win-64'");
+        assert!(!temp_dir.path().join("conda-win-64-default.lock").exists());
+    }
+
+    #[tokio::test]
+    async fn test_export_filename_template() {
+        let temp_dir = tempdir().unwrap();
+
+        let args = test_args([
+            "--all-platforms",
+            "--environment",
+            "with-pypi",
+            "--write-pypi-requirements",
+            "--filename-template",
+            "{environment}.{platform}.{extension}",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        for file_name in [
+            "with-pypi.linux-64.lock",
+            "with-pypi.linux-64.txt",
+            "with-pypi.osx-arm64.lock",
+            "with-pypi.osx-arm64.txt",
+        ] {
+            assert!(temp_dir.path().join(file_name).is_file(), "{file_name}");
+        }
+
+        // Templates that could produce colliding file names are rejected
+        assert!(Args::try_parse_from([
+            "conda-explicit-spec",
+            "--filename-template",
+            "{environment}.{extension}"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_explicit_spec_sorted() {
+        let lock_file = test_lock_file();
+        let mut packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let urls = |packages: &[CondaPackage], sort| {
+            let order = PackageOrder {
+                sort_by: sort.then_some(SortKey::Name),
+                noarch_last: false,
+            };
+            let options = ExplicitSpecOptions {
+                order,
+                ..ExplicitSpecOptions::default()
+            };
+            build_explicit_spec(Platform::Linux64, packages, &options)
+                .unwrap()
+                .packages
+                .into_iter()
+                .map(|p| p.url.path_segments().unwrap().last().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let expected = vec![
+            "_libgcc_mutex-0.1-conda_forge.tar.bz2",
+            "bzip2-1.0.8-h4bc722e_7.conda",
+            "libzlib-1.3.1-h4ab18f5_1.conda",
+            "tzdata-2024a-h0c530f3_0.conda",
+        ];
+        assert_eq!(urls(&packages, true), expected);
+
+        // The order of the packages in the lock file doesn't matter
+        packages.reverse();
+        assert_eq!(urls(&packages, true), expected);
+        packages.swap(0, 2);
+        assert_eq!(urls(&packages, true), expected);
+
+        // Unless sorting is disabled
+        let unsorted = urls(&packages, false);
+        assert_ne!(unsorted, expected);
+        assert_eq!(unsorted.into_iter().sorted().collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn test_explicit_spec_noarch_last() {
+        let base_url = "https://conda.anaconda.org/conda-forge";
+        let lock_file = LockFile::from_str(&format!(
+            "version: 5
+environments:
+  default:
+    channels:
+    - url: https://conda.anaconda.org/conda-forge/
+    packages:
+      linux-64:
+      - conda: {base_url}/noarch/appdirs-1.4.4-pyh9f0ad1d_0.tar.bz2
+      - conda: {base_url}/linux-64/zlib-1.3.1-h4ab18f5_1.conda
+      - conda: {base_url}/linux-64/bzip2-1.0.8-h4bc722e_7.conda
+packages:
+- kind: conda
+  name: appdirs
+  version: 1.4.4
+  build: pyh9f0ad1d_0
+  subdir: noarch
+  url: {base_url}/noarch/appdirs-1.4.4-pyh9f0ad1d_0.tar.bz2
+  md5: {MD5}
+- kind: conda
+  name: zlib
+  version: 1.3.1
+  build: h4ab18f5_1
+  subdir: linux-64
+  url: {base_url}/linux-64/zlib-1.3.1-h4ab18f5_1.conda
+  md5: {MD5}
+- kind: conda
+  name: bzip2
+  version: 1.0.8
+  build: h4bc722e_7
+  subdir: linux-64
+  url: {base_url}/linux-64/bzip2-1.0.8-h4bc722e_7.conda
+  md5: {MD5}
+"
+        ))
+        .unwrap();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let names = |sort, noarch_last| {
+            let order = PackageOrder {
+                sort_by: sort.then_some(SortKey::Name),
+                noarch_last,
+            };
+            let options = ExplicitSpecOptions {
+                order,
+                ..ExplicitSpecOptions::default()
+            };
+            build_explicit_spec(Platform::Linux64, &packages, &options)
+                .unwrap()
+                .packages
+                .into_iter()
+                .map(|p| p.url.path_segments().unwrap().last().unwrap().to_owned())
+                .map(|file| file.split('-').next().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        // The noarch packages come after the linux-64 packages
+        assert_eq!(names(true, true), ["bzip2", "zlib", "appdirs"]);
+        assert_eq!(names(false, true), ["zlib", "bzip2", "appdirs"]);
+
+        // Unless reordering is disabled
+        assert_eq!(names(true, false), ["appdirs", "bzip2", "zlib"]);
+        assert_eq!(names(false, false), ["appdirs", "zlib", "bzip2"]);
+    }
+
+    #[test]
+    fn test_explicit_spec_insecure_url() {
+        let package = conda_package_with_url(
+            "http://mirror.internal/conda-forge/linux-64/foo-1.0-h123_0.conda",
+            Some(MD5),
+            None,
+        );
+
+        // Plain http is allowed by default
+        let ees = build_explicit_spec(
+            Platform::Linux64,
+            [&package],
+            &ExplicitSpecOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(ees.packages[0].url.scheme(), "http");
+
+        let deny_insecure = ExplicitSpecOptions {
+            deny_insecure: true,
+            ..ExplicitSpecOptions::default()
+        };
+        let err = build_explicit_spec(Platform::Linux64, [&package], &deny_insecure).unwrap_err();
+        assert!(err.to_string().contains("insecure http"), "{err}");
+
+        // Unless the url is rewritten to https
+        let rewritten = ExplicitSpecOptions {
+            channel_overrides: vec![parse_channel_override(
+                "http://mirror.internal/=https://mirror.internal/",
+            )
+            .unwrap()],
+            ..deny_insecure
+        };
+        assert!(build_explicit_spec(Platform::Linux64, [&package], &rewritten).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_export_annotate_packages() {
+        let temp_dir = tempdir().unwrap();
+        let export = |annotate: bool| {
+            let mut args = test_args([
+                "--platform",
+                "linux-64",
+                "--output-dir",
+                temp_dir.path().to_str().unwrap(),
+            ]);
+            args.annotate_packages = annotate;
+            let target = temp_dir.path().join("conda-linux-64-default.lock");
+            async move {
+                execute(test_project(), args).await.unwrap();
+                fs::read_to_string(target).unwrap()
+            }
+        };
+
+        let plain = export(false).await;
+        let annotated = export(true).await;
+        assert!(!plain.contains("license="));
+
+        // Every entry is preceded by its comment, otherwise nothing changes
+        let lines = annotated.lines().collect_vec();
+        let bzip2 = lines
+            .iter()
+            .position(|line| line.contains("/bzip2-1.0.8-h4bc722e_7.conda"))
+            .unwrap();
+        assert!(
+            lines[bzip2 - 1].starts_with("# bzip2 1.0.8 license=bzip2-1.0.6"),
+            "{annotated}"
+        );
+        let stripped = lines
+            .iter()
+            .filter(|line| !line.contains("license="))
+            .join("\n");
+        assert_eq!(format!("{}\n", stripped), plain);
+    }
+
+    #[test]
+    fn test_explicit_spec_sort_by() {
+        let lock_file = test_lock_file();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let names = |sort_by| {
+            let options = ExplicitSpecOptions {
+                order: PackageOrder {
+                    sort_by: Some(sort_by),
+                    noarch_last: false,
+                },
+                ..ExplicitSpecOptions::default()
+            };
+            build_explicit_spec(Platform::Linux64, &packages, &options)
+                .unwrap()
+                .packages
+                .into_iter()
+                .map(|p| p.url.path_segments().unwrap().last().unwrap().to_owned())
+                .map(|file| file.rsplitn(3, '-').last().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(SortKey::Name),
+            ["_libgcc_mutex", "bzip2", "libzlib", "tzdata"]
+        );
+        // The noarch url sorts after the linux-64 urls
+        assert_eq!(
+            names(SortKey::Url),
+            ["_libgcc_mutex", "bzip2", "libzlib", "tzdata"]
+        );
+        assert_eq!(
+            names(SortKey::Size),
+            ["_libgcc_mutex", "libzlib", "tzdata", "bzip2"]
+        );
+
+        // Packages with the same size fall back to the url
+        let base_url = "https://conda.anaconda.org/conda-forge/linux-64";
+        let same_size = [
+            conda_package_with_url(
+                &format!("{base_url}/foo-1.0-h123_0.tar.bz2"),
+                Some(MD5),
+                None,
+            ),
+            conda_package_with_url(&format!("{base_url}/foo-1.0-h123_0.conda"), Some(MD5), None),
+        ];
+        let options = ExplicitSpecOptions {
+            order: PackageOrder {
+                sort_by: Some(SortKey::Size),
+                noarch_last: false,
+            },
+            ..ExplicitSpecOptions::default()
+        };
+        let ees = build_explicit_spec(Platform::Linux64, &same_size, &options).unwrap();
+        assert!(ees.packages[0].url.path().ends_with(".conda"));
+
+        // Packages of unknown size come after all packages with a size
+        let unknown_size =
+            conda_package_with_url(&format!("{base_url}/aaa-1.0-h123_0.conda"), Some(MD5), None);
+        assert!(unknown_size.package_record().size.is_none());
+        let ees = build_explicit_spec(
+            Platform::Linux64,
+            [&unknown_size].into_iter().chain(&packages),
+            &options,
+        )
+        .unwrap();
+        assert_eq!(
+            ees.packages
+                .iter()
+                .map(|p| p.url.path_segments().unwrap().last().unwrap())
+                .collect::<Vec<_>>(),
+            [
+                "_libgcc_mutex-0.1-conda_forge.tar.bz2",
+                "libzlib-1.3.1-h4ab18f5_1.conda",
+                "tzdata-2024a-h0c530f3_0.conda",
+                "bzip2-1.0.8-h4bc722e_7.conda",
+                "aaa-1.0-h123_0.conda",
+            ]
+        );
+
+        // The default is to sort by url
+        let args = test_args([]);
+        assert_eq!(args.package_order().sort_by, Some(SortKey::Url));
+        let args = test_args(["--no-sort"]);
+        assert_eq!(args.package_order().sort_by, None);
+        assert!(
+            Args::try_parse_from(["conda-explicit-spec", "--no-sort", "--sort-by", "name"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_explicit_spec_allowed_channels() {
+        let lock_file = test_lock_file();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let build = |allowed: &[&str]| {
+            let options = ExplicitSpecOptions {
+                allowed_channels: allowed.iter().map(|url| Url::parse(url).unwrap()).collect(),
+                ..ExplicitSpecOptions::default()
+            };
+            build_explicit_spec(Platform::Linux64, &packages, &options)
+        };
+
+        // Without allowed channels all urls are accepted
+        assert!(build(&[]).is_ok());
+        assert!(build(&["https://conda.anaconda.org/conda-forge/"]).is_ok());
+
+        // All violators are listed
+        let err = build(&["https://conda.anaconda.org/conda-forge/linux-64/"]).unwrap_err();
+        assert!(err.to_string().contains("tzdata"), "{err}");
+        let err = build(&["https://prefix.dev/conda-forge/"]).unwrap_err();
+        for name in ["_libgcc_mutex", "bzip2", "libzlib", "tzdata"] {
+            assert!(err.to_string().contains(name), "{err}");
+        }
+    }
+
+    #[test]
+    fn test_explicit_spec_round_trip() {
+        let base_url = "https://conda.anaconda.org/conda-forge/linux-64";
+        let packages = [
+            conda_package_with_url(&format!("{base_url}/foo-1.0-h123_0.conda"), Some(MD5), None),
+            conda_package_with_url(
+                &format!("{base_url}/foo-1.0-h123_0.tar.bz2"),
+                None,
+                Some(SHA256),
+            ),
+        ];
+        let ees = build_explicit_spec(
+            Platform::Linux64,
+            &packages,
+            &ExplicitSpecOptions::default(),
+        )
+        .unwrap();
+
+        let temp_dir = tempdir().unwrap();
+        for header in [GENERATED_BY_HEADER, ""] {
+            let target = temp_dir.path().join("conda-linux-64-default.lock");
+            write_export_file(&target, &render_explicit_spec(&ees, header)).unwrap();
+
+            let parsed = ExplicitEnvironmentSpec::from_path(&target).unwrap();
+            assert_eq!(parsed.platform, Some(Platform::Linux64));
+            assert_eq!(
+                parsed
+                    .packages
+                    .iter()
+                    .map(|p| p.url.as_str())
+                    .collect::<Vec<_>>(),
+                vec![
+                    format!("{base_url}/foo-1.0-h123_0.conda#{MD5}"),
+                    format!("{base_url}/foo-1.0-h123_0.tar.bz2#sha256:{SHA256}"),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_filter_conda_packages() {
+        let lock_file = test_lock_file();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let names = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| parse_package_pattern(name).unwrap())
+                .collect_vec()
+        };
+
+        let filtered = filter_conda_packages(
+            packages.clone(),
+            &names(&["bzip2", "TZDATA"]),
+            Platform::Linux64,
+        )
+        .unwrap();
+        assert_eq!(
+            filtered
+                .iter()
+                .map(|p| p.package_record().name.as_normalized())
+                .collect_vec(),
+            vec!["bzip2", "tzdata"]
+        );
+
+        // Glob patterns select a family of packages
+        let filtered = filter_conda_packages(
+            packages.clone(),
+            &names(&["bz*", "TZ?ATA"]),
+            Platform::Linux64,
+        )
+        .unwrap();
+        assert_eq!(filtered.len(), 2);
+
+        let err = filter_conda_packages(
+            packages.clone(),
+            &names(&["bz*", "numpy*"]),
+            Platform::Linux64,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the following packages are not locked for linux-64: numpy*"
+        );
+
+        let err = filter_conda_packages(packages, &names(&["bzip2", "numpy"]), Platform::Linux64)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "the following packages are not locked for linux-64: numpy"
+        );
+    }
+
+    #[test]
+    fn test_dependency_closure() {
+        let package = |name: &str, depends: &[&str]| {
+            format!(
+                "- kind: conda
+  name: {name}
+  version: '1.0'
+  build: h123_0
+  subdir: linux-64
+  url: https://conda.anaconda.org/conda-forge/linux-64/{name}-1.0-h123_0.conda
+  md5: {MD5}
+  depends: [{}]
+",
+                depends.iter().map(|d| format!("'{d}'")).join(", ")
+            )
+        };
+        let names = ["scipy", "numpy", "libblas", "python", "unrelated"];
+        let lock_file = format!(
+            "version: 5
+environments:
+  default:
+    channels:
+    - url: https://conda.anaconda.org/conda-forge/
+    packages:
+      linux-64:
+{}
+packages:
+{}{}{}{}{}",
+            names
+                .iter()
+                .map(|name| format!(
+                    "      - conda: https://conda.anaconda.org/conda-forge/linux-64/{name}-1.0-h123_0.conda"
+                ))
+                .join("\n"),
+            package("scipy", &["numpy >=1.23", "libblas", "__glibc >=2.17"]),
+            package("numpy", &["python >=3.9", "libblas"]),
+            package("libblas", &[]),
+            package("python", &[]),
+            package("unrelated", &["python"]),
+        );
+        let lock_file = LockFile::from_str(&lock_file).unwrap();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let closure = |roots: &[&str]| {
+            let roots = roots
+                .iter()
+                .map(|root| PackageName::from_str(root).unwrap())
+                .collect_vec();
+            dependency_closure(packages.clone(), &roots, Platform::Linux64).map(|packages| {
+                packages
+                    .iter()
+                    .map(|p| p.package_record().name.as_normalized().to_string())
+                    .sorted()
+                    .collect_vec()
+            })
+        };
+
+        assert_eq!(
+            closure(&["scipy"]).unwrap(),
+            ["libblas", "numpy", "python", "scipy"]
+        );
+        assert_eq!(closure(&["libblas"]).unwrap(), ["libblas"]);
+        assert_eq!(
+            closure(&["libblas", "unrelated"]).unwrap(),
+            ["libblas", "python", "unrelated"]
+        );
+        assert_eq!(
+            closure(&["pandas"]).unwrap_err().to_string(),
+            "the following packages are not locked for linux-64: pandas"
+        );
+    }
+
+    #[test]
+    fn test_pypi_requirements_direct_url() {
+        let url = "direct+https://example.com/six-1.16.0-py2.py3-none-any.whl";
+        let mut package = pypi_package_with_hashes(None);
+        package.url_or_path = UrlOrPath::Url(url.parse().unwrap());
+        let mut doubled = pypi_package_with_hashes(None);
+        doubled.url_or_path = UrlOrPath::Url(format!("direct+{url}").parse().unwrap());
+
+        let requirements = render_pypi_requirements(
+            Path::new("requirements.txt"),
+            &[package, doubled],
+            PypiHashAlgorithm::None,
+            Path::new(""),
+            false,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+
+        assert_eq!(
+            requirements,
+            format!("{}\n{}\n", &url["direct+".len()..], url)
+        );
+    }
+
+    #[test]
+    fn test_require_pypi_hashes() {
+        let sha256 = parse_digest_from_hex::<Sha256>(SHA256).unwrap();
+        let hashed = pypi_package_with_hashes(Some(PackageHashes::Sha256(sha256)));
+        let unhashed = pypi_package_with_hashes(None);
+        let mut local = pypi_package_with_hashes(None);
+        local.url_or_path = UrlOrPath::Path(PathBuf::from("./six"));
+
+        assert!(ensure_pypi_hashes(&[hashed.clone(), local]).is_ok());
+        assert_eq!(
+            ensure_pypi_hashes(&[hashed, unhashed])
+                .unwrap_err()
+                .to_string(),
+            "the following PyPI packages have no hash: six"
+        );
+
+        // The flag only makes sense when writing a requirements file
+        assert!(Args::try_parse_from(["conda-explicit-spec", "--require-pypi-hashes"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_json() {
+        let temp_dir = tempdir().unwrap();
+
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--format",
+            "json",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        let contents =
+            fs::read_to_string(temp_dir.path().join("conda-linux-64-default.json")).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&contents).unwrap();
+        assert_eq!(json["platform"], "linux-64");
+        assert_eq!(json["environment"], "default");
+
+        let packages = json["packages"].as_array().unwrap();
+        assert_eq!(packages.len(), 4);
+        assert_eq!(
+            packages[1],
+            serde_json::json!({
+                "name": "bzip2",
+                "version": "1.0.8",
+                "build": "h4bc722e_7",
+                "url": "https://conda.anaconda.org/conda-forge/linux-64/bzip2-1.0.8-h4bc722e_7.conda",
+                "md5": MD5,
+                "sha256": SHA256,
+            })
+        );
+        assert!(!temp_dir.path().join("conda-linux-64-default.lock").exists());
+    }
+
+    #[test]
+    fn test_json_spec_build_string() {
+        let lock_file = test_lock_file();
+        let packages = conda_packages(&lock_file, "default", Platform::Linux64);
+        let build = |include_build_string| {
+            let spec = build_json_spec(
+                "default",
+                Platform::Linux64,
+                &packages,
+                PackageOrder::default(),
+                include_build_string,
+                &[],
+            )
+            .unwrap();
+            serde_json::to_value(&spec).unwrap()["packages"][1]
+                .get("build")
+                .cloned()
+        };
+
+        assert_eq!(build(true), Some(serde_json::json!("h4bc722e_7")));
+        assert_eq!(build(false), None);
+
+        let args = test_args(["--include-build-string", "false"]);
+        assert!(!args.include_build_string);
+        assert!(test_args([]).include_build_string);
+    }
+
+    #[tokio::test]
+    async fn test_export_frozen_without_lock_file() {
+        let temp_dir = tempdir().unwrap();
+        let project_dir = temp_dir.path().join("project");
+        fs::create_dir(&project_dir).unwrap();
+        fs::copy(
+            test_project_dir().join("pixi.toml"),
+            project_dir.join("pixi.toml"),
+        )
+        .unwrap();
+        let project = Project::from_path(&project_dir.join("pixi.toml")).unwrap();
+
+        let args = Args::parse_from([
+            "conda-explicit-spec",
+            "--frozen",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        let err = execute(project, args).await.unwrap_err();
+
+        assert!(err.to_string().contains("does not exist"), "{err}");
+        assert!(!project_dir.join("pixi.lock").exists());
+    }
+
+    #[tokio::test]
+    async fn test_export_config() {
+        let temp_dir = tempdir().unwrap();
+        let project_dir = temp_dir.path().join("project");
+        fs::create_dir(&project_dir).unwrap();
+        for file in ["pixi.toml", "pixi.lock"] {
+            fs::copy(test_project_dir().join(file), project_dir.join(file)).unwrap();
+        }
+        fs::write(
+            project_dir.join(".pixi-export.toml"),
+            "environment = \"with-pypi\"\nplatform = \"osx-arm64\"\noutput-dir = \"dist\"\n",
+        )
+        .unwrap();
+        let project = || Project::from_path(&project_dir.join("pixi.toml")).unwrap();
+
+        execute(project(), test_args(["--ignore-pypi-errors"]))
+            .await
+            .unwrap();
+        assert!(project_dir
+            .join("dist")
+            .join("conda-osx-arm64-with-pypi.lock")
+            .is_file());
+
+        // The command line arguments take precedence
+        let output_dir = temp_dir.path().join("out");
+        let args = test_args([
+            "--environment",
+            "default",
+            "--platform",
+            "linux-64",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ]);
+        execute(project(), args).await.unwrap();
+        assert!(output_dir.join("conda-linux-64-default.lock").is_file());
+
+        // Enabling all platforms in the config is checked like the flag
+        fs::write(
+            project_dir.join(".pixi-export.toml"),
+            "all-platforms = true\n",
+        )
+        .unwrap();
+        let args = test_args([
+            "--conda-output",
+            output_dir.join("env.lock").to_str().unwrap(),
+        ]);
+        let err = execute(project(), args).await.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "a single path can't hold the files of all platforms: `--conda-output`"
+        );
+        assert!(!output_dir.join("env.lock").exists());
+        let args = test_args(["--merged", "--output-dir", output_dir.to_str().unwrap()]);
+        execute(project(), args).await.unwrap();
+        assert!(output_dir.join("conda-merged-default.lock").is_file());
+        let err = execute(project(), test_args(["--merged", "--platform", "linux-64"]))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "these options need all platforms to be exported: `--merged`"
+        );
+
+        // Unknown keys are rejected
+        fs::write(
+            project_dir.join(".pixi-export.toml"),
+            "platfrom = \"linux-64\"\n",
+        )
+        .unwrap();
+        assert!(execute(project(), test_args([])).await.is_err());
+    }
+
+    #[test]
+    fn test_pypi_requirements_relative_paths() {
+        let temp_dir = tempdir().unwrap();
+        let output_dir = temp_dir.path().join("dist");
+
+        let mut relative = pypi_package_with_hashes(None);
+        relative.url_or_path = UrlOrPath::Path(PathBuf::from("pkgs/foo"));
+        let mut absolute = pypi_package_with_hashes(None);
+        absolute.url_or_path = UrlOrPath::Path(temp_dir.path().join("pkgs").join("bar"));
+        absolute.editable = true;
+        let packages = [relative, absolute];
+
+        let target = output_dir.join("requirements.txt");
+        let requirements = render_pypi_requirements(
+            &target,
+            &packages,
+            PypiHashAlgorithm::Sha256,
+            temp_dir.path(),
+            true,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+        let expected = format!(
+            "{}\n-e ../pkgs/bar\n",
+            Path::new("..").join("pkgs").join("foo").display(),
+        );
+        assert_eq!(requirements, expected);
+
+        // Without relative paths the paths from the lock file are kept
+        let requirements = render_pypi_requirements(
+            &target,
+            &packages[..1],
+            PypiHashAlgorithm::Sha256,
+            temp_dir.path(),
+            false,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(requirements, "pkgs/foo\n");
+    }
+
+    #[test]
+    fn test_is_source_pypi_package() {
+        let wheel = pypi_package_with_hashes(None);
+        assert!(!is_source_pypi_package(&wheel));
+
+        let mut sdist = pypi_package_with_hashes(None);
+        sdist.url_or_path = UrlOrPath::Url(
+            "https://files.pythonhosted.org/packages/six-1.16.0.tar.gz"
+                .parse()
+                .unwrap(),
+        );
+        assert!(is_source_pypi_package(&sdist));
+
+        let mut local = pypi_package_with_hashes(None);
+        local.url_or_path = UrlOrPath::Path(PathBuf::from("pkgs/foo"));
+        assert!(is_source_pypi_package(&local));
+        local.url_or_path = UrlOrPath::Path(PathBuf::from("dist/foo-1.0-py3-none-any.whl"));
+        assert!(!is_source_pypi_package(&local));
+    }
+
+    #[test]
+    fn test_pypi_requirements_escaped_paths() {
+        let wheel = std::env::temp_dir()
+            .join("wheels dir")
+            .join("six-1.16.0-py2.py3-none-any.whl");
+        let packages = [
+            pypi_package_with_url(UrlOrPath::Path(PathBuf::from("pkgs/my pkg")), None, true),
+            pypi_package_with_url(UrlOrPath::Path(PathBuf::from("pkgs/ünï cödé")), None, true),
+            pypi_package_with_url(UrlOrPath::Path(PathBuf::from("pkgs/ünïcödé")), None, true),
+            pypi_package_with_url(UrlOrPath::Path(wheel.clone()), None, false),
+        ];
+
+        let requirements = render_pypi_requirements(
+            Path::new("requirements.txt"),
+            &packages,
+            PypiHashAlgorithm::Sha256,
+            Path::new(""),
+            false,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+        let lines = requirements.lines().collect_vec();
+        assert_eq!(lines[0], "-e \"pkgs/my pkg\"");
+        assert_eq!(lines[1], "-e \"pkgs/ünï cödé\"");
+        // Paths without special characters are kept as-is
+        assert_eq!(lines[2], "-e pkgs/ünïcödé");
+        let url = Url::from_file_path(&wheel).unwrap();
+        assert_eq!(lines[3], url.as_str());
+        assert!(url.as_str().contains("wheels%20dir"));
+        assert_eq!(url.to_file_path().unwrap(), wheel);
+    }
+
+    #[test]
+    fn test_pypi_requirements_escaped_relative_paths() {
+        // `--relative-paths` with `--output-dir dist` and a lock file path
+        // with a space, which is written as a file url of its location
+        let temp_dir = tempdir().unwrap();
+        let project_root = temp_dir.path().join("project");
+        let target = temp_dir.path().join("dist").join("requirements.txt");
+        let packages = [
+            pypi_package_with_url(UrlOrPath::Path(PathBuf::from("pkgs/my pkg")), None, false),
+            pypi_package_with_url(UrlOrPath::Path(PathBuf::from("pkgs/my pkg")), None, true),
+        ];
+
+        let requirements = render_pypi_requirements(
+            &target,
+            &packages,
+            PypiHashAlgorithm::Sha256,
+            &project_root,
+            true,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+        let url = Url::from_file_path(project_root.join("pkgs").join("my pkg")).unwrap();
+        assert_eq!(
+            requirements,
+            format!("{url}\n-e \"../project/pkgs/my pkg\"\n")
+        );
+
+        // Without relative paths the location is still the project root, not
+        // the current directory
+        let requirements = render_pypi_requirements(
+            &target,
+            &packages[..1],
+            PypiHashAlgorithm::Sha256,
+            &project_root,
+            false,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+        assert_eq!(requirements, format!("{url}\n"));
+    }
+
+    #[test]
+    fn test_pypi_requirements_editable_windows_path() {
+        let editable = pypi_package_with_url(
+            UrlOrPath::Path(PathBuf::from(r"C:\Users\me\pkgs\foo")),
+            None,
+            true,
+        );
+        let url = pypi_package_with_url(
+            UrlOrPath::Url(Url::parse("file:///C:/Users/me/pkgs/bar").unwrap()),
+            None,
+            true,
+        );
+
+        let requirements = render_pypi_requirements(
+            Path::new("requirements.txt"),
+            &[editable, url],
+            PypiHashAlgorithm::Sha256,
+            Path::new(""),
+            false,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+        let mut lines = requirements.lines();
+        assert_eq!(lines.next(), Some("-e C:/Users/me/pkgs/foo"));
+        // Url based editables are kept as-is
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("-e file:///C:/Users/me/pkgs/bar"));
+    }
+
+    #[test]
+    fn test_pypi_requirements_local_hashes() {
+        let sha256 = parse_digest_from_hex::<Sha256>(SHA256).unwrap();
+        let archive = pypi_package_with_url(
+            UrlOrPath::Path(PathBuf::from("dist/six-1.16.0-py2.py3-none-any.whl")),
+            Some(PackageHashes::Sha256(sha256)),
+            false,
+        );
+        let editable_url = pypi_package_with_url(
+            UrlOrPath::Url(Url::parse("https://example.com/six-1.16.0.tar.gz").unwrap()),
+            Some(PackageHashes::Sha256(sha256)),
+            true,
+        );
+
+        let requirements = render_pypi_requirements(
+            Path::new("requirements.txt"),
+            &[archive, editable_url],
+            PypiHashAlgorithm::Sha256,
+            Path::new(""),
+            false,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+        // pip rejects hashes for local files, but urls keep them
+        assert_eq!(
+            requirements,
+            format!(
+                "dist/six-1.16.0-py2.py3-none-any.whl\n\
+                -e https://example.com/six-1.16.0.tar.gz --hash=sha256:{SHA256}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_pypi_requirements_python_marker() {
+        let mut package = pypi_package_with_hashes(None);
+        package.requires_python = Some(VersionSpecifiers::from_str(">=3.8,<4,!=3.9.*").unwrap());
+        let mut patch = pypi_package_with_hashes(None);
+        patch.requires_python = Some(VersionSpecifiers::from_str(">=3.8.1").unwrap());
+        let mut local = pypi_package_with_hashes(None);
+        local.url_or_path = UrlOrPath::Path(PathBuf::from("./six"));
+        local.requires_python = Some(VersionSpecifiers::from_str(">=3.8").unwrap());
+
+        let requirements = render_pypi_requirements(
+            Path::new("requirements.txt"),
+            &[package, patch, local, pypi_package_with_hashes(None)],
+            PypiHashAlgorithm::None,
+            Path::new(""),
+            false,
+            None,
+            &[],
+            &[],
+        )
+        .unwrap();
+
+        let url = "https://files.pythonhosted.org/packages/six-1.16.0-py2.py3-none-any.whl";
+        assert_eq!(
+            requirements,
+            format!(
+                "{url} ; python_version >= \"3.8\" and python_version < \"4\" and python_version != \"3.9.*\"\n\
+                {url} ; python_full_version >= \"3.8.1\"\n\
+                ./six ; python_version >= \"3.8\"\n\
+                {url}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn test_pypi_requirements_index_urls() {
+        let index_url = Url::parse("https://pypi.internal/simple").unwrap();
+        let extra_index_urls = [
+            Url::parse("https://extra.internal/simple").unwrap(),
+            Url::parse("https://other.internal/simple").unwrap(),
+        ];
+
+        let requirements = render_pypi_requirements(
+            Path::new("requirements.txt"),
+            &[pypi_package_with_hashes(None)],
+            PypiHashAlgorithm::Sha256,
+            Path::new(""),
+            false,
+            Some(&index_url),
+            &extra_index_urls,
+            &["--prefer-binary".to_string(), "--no-deps".to_string()],
+        )
+        .unwrap();
+        assert_eq!(
+            requirements,
+            "--prefer-binary\n\
+            --no-deps\n\
+            --index-url https://pypi.internal/simple\n\
+            --extra-index-url https://extra.internal/simple\n\
+            --extra-index-url https://other.internal/simple\n\
+            https://files.pythonhosted.org/packages/six-1.16.0-py2.py3-none-any.whl\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_pip_option() {
+        let args = test_args([
+            "--write-pypi-requirements",
+            "--pip-option",
+            "--prefer-binary",
+            "--pip-option=--no-deps",
+        ]);
+        assert_eq!(args.pip_option, ["--prefer-binary", "--no-deps"]);
+
+        assert!(parse_pip_option("numpy").is_err());
+        assert!(Args::try_parse_from([
+            "conda-explicit-spec",
+            "--write-pypi-requirements",
+            "--pip-option",
+            "numpy"
+        ])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_merged() {
+        let temp_dir = tempdir().unwrap();
+
+        let args = test_args([
+            "--all-platforms",
+            "--merged",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        assert!(!temp_dir.path().join("conda-linux-64-default.lock").exists());
+        let merged = fs::read_to_string(temp_dir.path().join("conda-merged-default.lock")).unwrap();
+        assert!(merged.starts_with("# Generated by `pixi project export`\n"));
+        assert_eq!(merged.matches("# Generated by").count(), 1);
+
+        // Every platform has its own block, preceded by the shared noarch block
+        let platforms = merged
+            .lines()
+            .filter_map(|line| line.strip_prefix("# platform: "))
+            .collect_vec();
+        assert_eq!(platforms, vec!["noarch", "linux-64", "osx-arm64"]);
+        assert_eq!(merged.matches("@EXPLICIT").count(), 3);
+
+        // tzdata is noarch and locked for both platforms so it is written once
+        assert_eq!(
+            merged
+                .matches("/noarch/tzdata-2024a-h0c530f3_0.conda")
+                .count(),
+            1
+        );
+        assert_eq!(merged.matches("/bzip2-1.0.8-").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_project_export_conda_explicit_spec() {
+        let project = test_project();
+        let environment = project.environment("default").unwrap();
+        let options = ExplicitSpecOptions {
+            lock_file_usage: LockFileUsage::Frozen,
+            packages: vec![glob::Pattern::new("bzip2").unwrap()],
+            ..ExplicitSpecOptions::default()
+        };
+
+        let ees = project
+            .export_conda_explicit_spec(&environment, Platform::Linux64, options)
+            .await
+            .unwrap();
+        assert_eq!(ees.platform, Some(Platform::Linux64));
+        assert_eq!(
+            ees.packages.iter().map(|p| p.url.as_str()).collect_vec(),
+            vec![format!(
+                "https://conda.anaconda.org/conda-forge/linux-64/bzip2-1.0.8-h4bc722e_7.conda#{MD5}"
+            )]
+        );
+
+        // PyPI packages are an error unless they are ignored
+        let environment = project.environment("with-pypi").unwrap();
+        let options = ExplicitSpecOptions {
+            lock_file_usage: LockFileUsage::Frozen,
+            ..ExplicitSpecOptions::default()
+        };
+        assert!(project
+            .export_conda_explicit_spec(&environment, Platform::Linux64, options.clone())
+            .await
+            .is_err());
+        let ees = project
+            .export_conda_explicit_spec(
+                &environment,
+                Platform::Linux64,
+                ExplicitSpecOptions {
+                    ignore_pypi_packages: true,
+                    ..options
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(ees.packages.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_project_locked_packages() {
+        let project = test_project();
+        let environment = project.environment("with-pypi").unwrap();
+        let options = ExplicitSpecOptions {
+            lock_file_usage: LockFileUsage::Frozen,
+            ..ExplicitSpecOptions::default()
+        };
+
+        let (conda_packages, pypi_packages) = project
+            .locked_packages(&environment, Platform::Linux64, &options)
+            .await
+            .unwrap();
+        assert_eq!(
+            conda_packages
+                .iter()
+                .map(|p| p.package_record().name.as_normalized())
+                .sorted()
+                .collect_vec(),
+            ["_libgcc_mutex", "bzip2", "libzlib", "tzdata"]
+        );
+        assert_eq!(
+            pypi_packages
+                .iter()
+                .map(|p| p.name.to_string())
+                .collect_vec(),
+            ["six"]
+        );
+
+        // PyPI packages are dropped when they are ignored
+        let ignored = ExplicitSpecOptions {
+            ignore_pypi_packages: true,
+            ..options.clone()
+        };
+        let (_, pypi_packages) = project
+            .locked_packages(&environment, Platform::Linux64, &ignored)
+            .await
+            .unwrap();
+        assert!(pypi_packages.is_empty());
+
+        // A platform that is not part of the environment is an error
+        let err = project
+            .locked_packages(&environment, Platform::Win64, &options)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("win-64"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_export_compressed() {
+        let temp_dir = tempdir().unwrap();
+        let export = |extra: &'static [&'static str]| {
+            let args = test_args(
+                [
+                    "--platform",
+                    "linux-64",
+                    "--environment",
+                    "with-pypi",
+                    "--write-pypi-requirements",
+                    "--compress",
+                    "--output-dir",
+                    temp_dir.path().to_str().unwrap(),
+                ]
+                .into_iter()
+                .chain(extra.iter().copied()),
+            );
+            execute(test_project(), args)
+        };
+        let decompress = |file_name: &str| {
+            let file = fs::File::open(temp_dir.path().join(file_name)).unwrap();
+            let mut contents = String::new();
+            std::io::Read::read_to_string(&mut flate2::read::GzDecoder::new(file), &mut contents)
+                .unwrap();
+            contents
+        };
+
+        export(&[]).await.unwrap();
+        assert!(decompress("conda-linux-64-with-pypi.lock.gz").contains("@EXPLICIT"));
+        assert!(decompress("requirements-linux-64-with-pypi.txt.gz").contains("six-1.16.0"));
+
+        // A template that already ends with `.gz` doesn't get a second suffix
+        export(&[
+            "--filename-template",
+            "{environment}-{platform}.{extension}.gz",
+        ])
+        .await
+        .unwrap();
+        assert!(decompress("with-pypi-linux-64.lock.gz").contains("@EXPLICIT"));
+        assert!(!temp_dir
+            .path()
+            .join("with-pypi-linux-64.lock.gz.gz")
+            .exists());
+    }
+
+    #[test]
+    fn test_checksum_line() {
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("conda-linux-64-default.lock");
+        fs::write(&target, "hello").unwrap();
+
+        assert_eq!(
+            checksum_line(&target).unwrap(),
+            format!(
+                "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  {}",
+                target.display()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_extension() {
+        let temp_dir = tempdir().unwrap();
+
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--extension",
+            "txt",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        let spec = fs::read_to_string(temp_dir.path().join("conda-linux-64-default.txt")).unwrap();
+        assert!(spec.contains("@EXPLICIT"));
+        assert!(!temp_dir.path().join("conda-linux-64-default.lock").exists());
+
+        assert_eq!(parse_extension(".txt").unwrap(), "txt");
+        assert!(parse_extension("").is_err());
+        assert!(parse_extension("../txt").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_name_override() {
+        let temp_dir = tempdir().unwrap();
+
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--name",
+            "testenv",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        assert!(temp_dir
+            .path()
+            .join("conda-linux-64-testenv.lock")
+            .is_file());
+        assert!(!temp_dir.path().join("conda-linux-64-default.lock").exists());
+
+        assert!(Args::try_parse_from(["conda-explicit-spec", "--name", "../testenv"]).is_err());
+    }
+
+    #[test]
+    fn test_sorted_pypi_packages() {
+        assert_eq!(canonical_pypi_name("Zope.Interface"), "zope-interface");
+        assert_eq!(
+            canonical_pypi_name("typing__extensions"),
+            "typing-extensions"
+        );
+        assert_eq!(canonical_pypi_name("six"), "six");
+
+        let mut numpy = pypi_package_with_url(
+            UrlOrPath::Url(
+                Url::parse("https://files.pythonhosted.org/packages/numpy-2.0.0-cp312-cp312-linux_x86_64.whl")
+                    .unwrap(),
+            ),
+            None,
+            false,
+        );
+        numpy.name = "numpy".parse().unwrap();
+        let mut local = pypi_package_with_url(
+            UrlOrPath::Path(PathBuf::from("pkgs/Zope.Interface")),
+            None,
+            true,
+        );
+        local.name = "zope-interface".parse().unwrap();
+        let packages = [local, pypi_package_with_hashes(None), numpy];
+
+        let names = |packages: Vec<PypiPackageData>| {
+            packages.iter().map(|p| p.name.to_string()).collect_vec()
+        };
+        let sorted = sorted_pypi_packages(&test_args([]), &packages, requirement_sort_key);
+        assert_eq!(names(sorted), ["numpy", "six", "zope-interface"]);
+        let unsorted = sorted_pypi_packages(
+            &test_args(["--no-sort-pypi"]),
+            &packages,
+            requirement_sort_key,
+        );
+        assert_eq!(names(unsorted), ["zope-interface", "six", "numpy"]);
+    }
+
+    #[test]
+    fn test_render_pypi_constraints() {
+        let mut local = pypi_package_with_hashes(None);
+        local.name = "local".parse().unwrap();
+        local.url_or_path = UrlOrPath::Path(PathBuf::from("./local"));
+        let mut editable = pypi_package_with_hashes(None);
+        editable.name = "editable".parse().unwrap();
+        editable.editable = true;
+        let packages = [pypi_package_with_hashes(None), local, editable];
+
+        assert_eq!(render_pypi_constraints(&packages), "six==1.16.0\n");
+    }
+
+    #[test]
+    fn test_platform_suggestions() {
+        assert_eq!(
+            parse_platform("linux64").unwrap_err(),
+            "'linux64' is not a known platform, did you mean 'linux-64'?"
+        );
+        assert_eq!(
+            parse_platform("foobar").unwrap_err(),
+            "'foobar' is not a known platform"
+        );
+        assert_eq!(parse_platform("osx-arm64").unwrap(), Platform::OsxArm64);
+
+        // Aliases resolve to the canonical platform
+        assert_eq!(parse_platform("macos-arm64").unwrap(), Platform::OsxArm64);
+        assert_eq!(parse_platform("arm64").unwrap(), Platform::OsxArm64);
+        assert_eq!(parse_platform("Linux-x86_64").unwrap(), Platform::Linux64);
+        assert_eq!(parse_platform("win-amd64").unwrap(), Platform::Win64);
+        let args =
+            Args::try_parse_from(["conda-explicit-spec", "--platform", "macos-arm64"]).unwrap();
+        assert_eq!(args.platform().unwrap(), Some(Platform::OsxArm64));
+        assert!(Args::try_parse_from(["conda-explicit-spec", "--platform", "linux64"]).is_err());
+
+        assert_eq!(
+            closest_platform("osx-64", [Platform::Linux64, Platform::OsxArm64]),
+            Some(Platform::OsxArm64)
+        );
+        assert_eq!(closest_platform("freebsd-64", [Platform::Linux64]), None);
+    }
+
+    #[tokio::test]
+    async fn test_export_explicit_output_paths() {
+        let temp_dir = tempdir().unwrap();
+        // The parent directories of the paths are created
+        let conda_output = temp_dir.path().join("conda").join("env.lock");
+        let pypi_output = temp_dir.path().join("pip").join("pip.txt");
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--environment",
+            "with-pypi",
+            "--write-pypi-requirements",
+            "--write-pypi-constraints",
+            "--conda-output",
+            conda_output.to_str().unwrap(),
+            "--pypi-output",
+            pypi_output.to_str().unwrap(),
+            "--output-dir",
+            temp_dir.path().join("dist").to_str().unwrap(),
+        ]);
+        let written = execute(test_project(), args).await.unwrap();
+        assert_eq!(
+            written,
+            [
+                conda_output.clone(),
+                pypi_output.clone(),
+                temp_dir
+                    .path()
+                    .join("dist")
+                    .join("constraints-linux-64-with-pypi.txt")
+            ]
+        );
+        assert_eq!(fs::read_to_string(&pypi_output).unwrap(), SIX_REQUIREMENT);
+        assert_eq!(
+            fs::read_to_string(
+                temp_dir
+                    .path()
+                    .join("dist")
+                    .join("constraints-linux-64-with-pypi.txt")
+            )
+            .unwrap(),
+            "six==1.16.0\n"
+        );
+
+        // The conda file is the same as the one written to the output dir
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--environment",
+            "with-pypi",
+            "--write-pypi-requirements",
+            "--output-dir",
+            temp_dir.path().join("dist").to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(&conda_output).unwrap(),
+            fs::read_to_string(
+                temp_dir
+                    .path()
+                    .join("dist")
+                    .join("conda-linux-64-with-pypi.lock")
+            )
+            .unwrap()
+        );
+
+        // A single path can't hold multiple platforms
+        for flag in ["--all-platforms", "--platform-from-lockfile"] {
+            assert!(Args::try_parse_from([
+                "conda-explicit-spec",
+                flag,
+                "--conda-output",
+                "env.lock"
+            ])
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn test_merge_explicit_spec() {
+        let existing = "# platform: linux-64\r\n@EXPLICIT\r\n\
+            https://x.org/c/noarch/tzdata-1-0.conda#1\r\n\
+            https://x.org/c/linux-64/zlib-1-0.conda#2\r\n";
+        let new = "# Generated by `pixi project export`\n\
+            # platform: linux-64\n\
+            @EXPLICIT\n\
+            https://x.org/c/linux-64/bzip2-1-0.conda#3\n\
+            https://x.org/c/linux-64/zlib-1-0.conda#4\n\
+            # pypi: six==1.16.0\n";
+
+        let (merged, added) = merge_explicit_spec(existing, new, PackageOrder::default());
+        assert_eq!(added, 1);
+        assert_eq!(
+            merged,
+            "# Generated by `pixi project export`\n\
+            # platform: linux-64\n\
+            @EXPLICIT\n\
+            https://x.org/c/linux-64/bzip2-1-0.conda#3\n\
+            https://x.org/c/linux-64/zlib-1-0.conda#4\n\
+            https://x.org/c/noarch/tzdata-1-0.conda#1\n\
+            # pypi: six==1.16.0\n"
+        );
+        assert_eq!(spec_platform(existing), Some("linux-64"));
+
+        // An empty export only gets the existing entries
+        let (merged, added) = merge_explicit_spec(existing, "@EXPLICIT\n", PackageOrder::default());
+        assert_eq!(added, 2);
+        assert_eq!(
+            merged,
+            "@EXPLICIT\n\
+            https://x.org/c/linux-64/zlib-1-0.conda#2\n\
+            https://x.org/c/noarch/tzdata-1-0.conda#1\n"
+        );
+
+        // The entries are ordered like a fresh export
+        let entries = |order| {
+            let (merged, _) = merge_explicit_spec(existing, new, order);
+            merged
+                .lines()
+                .filter(|line| is_spec_entry(line))
+                .map(|line| line.rsplit('/').next().unwrap().to_string())
+                .collect_vec()
+        };
+        assert_eq!(
+            entries(PackageOrder {
+                sort_by: None,
+                noarch_last: false,
+            }),
+            [
+                "bzip2-1-0.conda#3",
+                "zlib-1-0.conda#4",
+                "tzdata-1-0.conda#1"
+            ]
+        );
+        assert_eq!(
+            entries(PackageOrder {
+                sort_by: Some(SortKey::Url),
+                noarch_last: false,
+            }),
+            [
+                "bzip2-1-0.conda#3",
+                "zlib-1-0.conda#4",
+                "tzdata-1-0.conda#1"
+            ]
+        );
+        assert_eq!(
+            entries(PackageOrder {
+                sort_by: Some(SortKey::Name),
+                noarch_last: false,
+            }),
+            [
+                "bzip2-1-0.conda#3",
+                "tzdata-1-0.conda#1",
+                "zlib-1-0.conda#4"
+            ]
+        );
+        let existing = "@EXPLICIT\nhttps://a.org/c/noarch/tzdata-1-0.conda#1\n";
+        let (merged, _) = merge_explicit_spec(
+            existing,
+            new,
+            PackageOrder {
+                sort_by: Some(SortKey::Url),
+                noarch_last: false,
+            },
+        );
+        assert!(merged.contains("@EXPLICIT\nhttps://a.org/c/noarch/tzdata-1-0.conda#1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_export_append() {
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("layered.lock");
+        let export = |package: &str, append: bool| {
+            let mut args = test_args([
+                "--platform",
+                "linux-64",
+                "--package",
+                package,
+                "--conda-output",
+                target.to_str().unwrap(),
+            ]);
+            args.append = append;
+            execute(test_project(), args)
+        };
+
+        export("bzip2", false).await.unwrap();
+        export("libzlib", true).await.unwrap();
+        export("bzip2", true).await.unwrap();
+        let contents = fs::read_to_string(&target).unwrap();
+        assert_eq!(contents.matches("/bzip2-").count(), 1, "{contents}");
+        assert_eq!(contents.matches("/libzlib-").count(), 1, "{contents}");
+        assert_eq!(contents.matches(GENERATED_BY_HEADER).count(), 1);
+
+        // Without `--append` the spec is overwritten
+        export("bzip2", false).await.unwrap();
+        let contents = fs::read_to_string(&target).unwrap();
+        assert!(!contents.contains("/libzlib-"), "{contents}");
+
+        // The sizes of the existing packages are unknown
+        let mut args = test_args([
+            "--platform",
+            "linux-64",
+            "--sort-by",
+            "size",
+            "--conda-output",
+            target.to_str().unwrap(),
+        ]);
+        args.append = true;
+        let err = execute(test_project(), args).await.unwrap_err();
+        assert!(err.to_string().contains("`--sort-by size`"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_exit_code() {
+        let export = |extra: &[&str]| execute(test_project(), test_args(extra.iter().copied()));
+        let exit_code_of = |result: miette::Result<Vec<PathBuf>>| exit_code(&result.unwrap_err());
+
+        assert_eq!(
+            exit_code_of(export(&["--environment", "with-pypi", "--dry-run"]).await),
+            EXIT_PYPI_NOT_SUPPORTED
+        );
+        assert_eq!(
+            exit_code_of(export(&["--platform", "win-64", "--dry-run"]).await),
+            EXIT_PLATFORM_NOT_FOUND
+        );
+        assert_eq!(
+            exit_code_of(export(&["--environment", "unknown", "--dry-run"]).await),
+            EXIT_ENVIRONMENT_NOT_FOUND
+        );
+        assert_eq!(exit_code(&miette::miette!("failed")), 1);
+
+        // The exit code is returned to `main` instead of exiting the process
+        let err = export(&["--platform", "win-64", "--error-format", "json"])
+            .await
+            .unwrap_err();
+        let err = err.downcast_ref::<ExitCodeError>().unwrap();
+        assert_eq!(err.code, EXIT_PLATFORM_NOT_FOUND);
+        assert!(err.report.is_none());
+        let err = export(&["--platform", "win-64", "--dry-run"])
+            .await
+            .unwrap_err();
+        assert!(err
+            .downcast_ref::<ExitCodeError>()
+            .unwrap()
+            .report
+            .is_some());
+        assert_eq!(err.code().unwrap().to_string(), "unsupported-platform");
+    }
+
+    #[tokio::test]
+    async fn test_export_from_lockfile() {
+        // A snapshot of the lock file that only contains linux-64 of the
+        // default environment
+        let temp_dir = tempdir().unwrap();
+        let snapshot = temp_dir.path().join("snapshot.lock");
+        crate::cli::project::export::lock_fragment::build_lock_file_fragment(
+            &test_lock_file(),
+            "default",
+            Platform::Linux64,
+        )
+        .unwrap()
+        .to_path(&snapshot)
+        .unwrap();
+
+        let output_dir = temp_dir.path().join("dist");
+        let export = |extra: &[&str]| {
+            let args = test_args(
+                [
+                    "--lockfile",
+                    snapshot.to_str().unwrap(),
+                    "--output-dir",
+                    output_dir.to_str().unwrap(),
+                ]
+                .iter()
+                .chain(extra)
+                .copied(),
+            );
+            execute(test_project(), args)
+        };
+        let written = export(&["--platform", "linux-64"]).await.unwrap();
+        assert_eq!(written, [output_dir.join("conda-linux-64-default.lock")]);
+
+        let code = |err: miette::Report| err.code().map(|code| code.to_string());
+        let err = export(&["--platform", "osx-arm64"]).await.unwrap_err();
+        assert_eq!(code(err).as_deref(), Some("platform-not-locked"));
+        let err = export(&["--environment", "with-pypi"]).await.unwrap_err();
+        assert_eq!(code(err).as_deref(), Some("environment-not-locked"));
+
+        fs::write(&snapshot, "not a lock file").unwrap();
+        let err = export(&["--platform", "linux-64"]).await.unwrap_err();
+        assert_eq!(code(err).as_deref(), Some("lock-file-invalid"));
+    }
+
+    #[tokio::test]
+    async fn test_export_platform_from_lockfile() {
+        // A project where only linux-64 of the manifest platforms is locked
+        let temp_dir = tempdir().unwrap();
+        let project_dir = temp_dir.path().join("project");
+        fs::create_dir(&project_dir).unwrap();
+        fs::copy(
+            test_project_dir().join("pixi.toml"),
+            project_dir.join("pixi.toml"),
+        )
+        .unwrap();
+        crate::cli::project::export::lock_fragment::build_lock_file_fragment(
+            &test_lock_file(),
+            "default",
+            Platform::Linux64,
+        )
+        .unwrap()
+        .to_path(&project_dir.join("pixi.lock"))
+        .unwrap();
+        let project = || Project::from_path(&project_dir.join("pixi.toml")).unwrap();
+
+        let output_dir = temp_dir.path().join("dist");
+        let args = test_args([
+            "--platform-from-lockfile",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ]);
+        let written = execute(project(), args).await.unwrap();
+        assert_eq!(written, [output_dir.join("conda-linux-64-default.lock")]);
+
+        assert!(Args::try_parse_from([
+            "conda-explicit-spec",
+            "--platform-from-lockfile",
+            "--all-platforms"
+        ])
+        .is_err());
+        assert!(Args::try_parse_from([
+            "conda-explicit-spec",
+            "--platform-from-lockfile",
+            "--platform",
+            "linux-64"
+        ])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_all_platforms_partial_failure() {
+        // `_libgcc_mutex` is only locked for linux-64
+        let temp_dir = tempdir().unwrap();
+        let output_dir = temp_dir.path().to_str().unwrap();
+        let args = test_args([
+            "--all-platforms",
+            "--package",
+            "_libgcc_mutex",
+            "--output-dir",
+            output_dir,
+        ]);
+        let written = execute(test_project(), args).await.unwrap();
+        assert_eq!(
+            written,
+            [temp_dir.path().join("conda-linux-64-default.lock")]
+        );
+
+        let args = test_args([
+            "--all-platforms",
+            "--fail-fast",
+            "--package",
+            "_libgcc_mutex",
+            "--output-dir",
+            output_dir,
+        ]);
+        let err = execute(test_project(), args).await.unwrap_err();
+        assert!(err.to_string().contains("osx-arm64"), "{err}");
+
+        // It is still an error if no platform succeeds
+        let args = test_args([
+            "--all-platforms",
+            "--package",
+            "numpy",
+            "--output-dir",
+            output_dir,
+        ]);
+        let err = execute(test_project(), args).await.unwrap_err();
+        assert!(
+            err.to_string().contains("failed for all platforms"),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_newline() {
+        assert_eq!(Newline::Crlf.normalize("a\nb\r\nc"), "a\r\nb\r\nc");
+        assert_eq!(Newline::Lf.normalize("a\r\nb\n"), "a\nb\n");
+
+        let temp_dir = tempdir().unwrap();
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--environment",
+            "with-pypi",
+            "--write-pypi-requirements",
+            "--newline",
+            "crlf",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        for path in execute(test_project(), args).await.unwrap() {
+            let contents = fs::read_to_string(&path).unwrap();
+            assert!(contents.contains("\r\n"), "{}", path.display());
+            assert_eq!(
+                contents.matches('\n').count(),
+                contents.matches("\r\n").count()
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_json_error() {
+        let args = test_args(["--format", "json", "--all-platforms", "--merged"]);
+        let err = export(test_project(), args).await.unwrap_err();
+        let error = serde_json::to_value(JsonError::from_report(&err)).unwrap();
+        assert_eq!(error["code"], "unsupported-format");
+        assert_eq!(
+            error["message"],
+            "`--merged` is only supported for the explicit format"
+        );
+        assert!(error.get("help").is_none());
+
+        let err = export(test_project(), test_args(["--platform", "win-64"]))
+            .await
+            .unwrap_err();
+        let error = JsonError::from_report(&err);
+        assert_eq!(error.code, "unsupported-platform");
+        assert!(error.help.unwrap().contains("linux-64"));
+
+        let error = JsonError::from_report(&miette::miette!("something else"));
+        assert_eq!(error.code, "export-failed");
+        assert!(error.context.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_export_is_reproducible() {
+        let export = |output_dir: PathBuf| async move {
+            let args = test_args([
+                "--all-environments",
+                "--all-platforms",
+                "--write-pypi-requirements",
+                "--write-pypi-constraints",
+                "--annotate",
+                "--annotate-packages",
+                "--output-dir",
+                output_dir.to_str().unwrap(),
+            ]);
+            execute(test_project(), args)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|path| {
+                    let name = path.file_name().unwrap().to_owned();
+                    (name, fs::read(&path).unwrap())
+                })
+                .collect_vec()
+        };
+
+        let temp_dir = tempdir().unwrap();
+        let first = export(temp_dir.path().join("first")).await;
+        let second = export(temp_dir.path().join("second")).await;
+        assert!(!first.is_empty());
+        assert_eq!(first, second);
+
+        // With `--timestamp` the files are only reproducible with
+        // `SOURCE_DATE_EPOCH`, which is passed in directly since the
+        // environment is shared by the tests running in parallel
+        let project = test_project();
+        let environment = project.default_environment();
+        let header = |generated_at| {
+            explicit_spec_header(&test_args(["--timestamp"]), &environment, generated_at)
+        };
+        let pinned = header(export_time(Some("1700000000")));
+        assert_eq!(
+            pinned.lines().last(),
+            Some("# generated-at: 2023-11-14T22:13:20Z")
+        );
+        assert_eq!(header(export_time(Some("1700000000"))), pinned);
+        assert!(parse_source_date_epoch("yesterday").is_none());
+    }
+
+    #[test]
+    fn test_indent_lines() {
+        assert_eq!(
+            indent_lines("@EXPLICIT\n\nhttps://example.com/foo.conda\n", 4),
+            "    @EXPLICIT\n\n    https://example.com/foo.conda\n"
+        );
+        assert_eq!(
+            indent_lines("{\n  \"a\": 1\n}", 2),
+            "  {\n    \"a\": 1\n  }"
+        );
+
+        assert!(Args::try_parse_from(["conda-explicit-spec", "--indent", "4"]).is_err());
+        assert!(Args::try_parse_from(["conda-explicit-spec", "--stdout", "--indent", "4"]).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_export_post_export_command() {
+        let temp_dir = tempdir().unwrap();
+        let log = temp_dir.path().join("hook.log");
+        let export = |command: String| {
+            let args = test_args([
+                "--all-platforms",
+                "--post-export-command",
+                command.as_str(),
+                "--output-dir",
+                temp_dir.path().to_str().unwrap(),
+            ]);
+            execute(test_project(), args)
+        };
+
+        export(format!(
+            "test -f \"$PIXI_EXPORT_FILE\" && echo \"$PIXI_EXPORT_PLATFORM $PIXI_EXPORT_ENVIRONMENT\" >> {}",
+            log.display()
+        ))
+        .await
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "linux-64 default\nosx-arm64 default\n"
+        );
+
+        let err = export("exit 3".to_string()).await.unwrap_err();
+        assert!(err.to_string().contains("failed"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_export_no_clobber() {
+        let temp_dir = tempdir().unwrap();
+        let export = |no_clobber: bool| {
+            let mut args = test_args([
+                "--all-platforms",
+                "--environment",
+                "with-pypi",
+                "--write-pypi-requirements",
+                "--output-dir",
+                temp_dir.path().to_str().unwrap(),
+            ]);
+            args.no_clobber = no_clobber;
+            execute(test_project(), args)
+        };
+
+        // Only the pypi requirements of one platform exist
+        let requirements = temp_dir.path().join("requirements-osx-arm64-with-pypi.txt");
+        fs::write(&requirements, "existing").unwrap();
+
+        let err = export(true).await.unwrap_err();
+        assert!(
+            err.to_string()
+                .contains(&requirements.display().to_string()),
+            "{err}"
+        );
+        assert_eq!(fs::read_to_string(&requirements).unwrap(), "existing");
+        assert!(!temp_dir
+            .path()
+            .join("conda-linux-64-with-pypi.lock")
+            .exists());
+
+        // Overwriting is the default
+        export(false).await.unwrap();
+        assert_ne!(fs::read_to_string(&requirements).unwrap(), "existing");
+    }
+
+    #[tokio::test]
+    async fn test_export_loads_lock_file_once() {
+        let temp_dir = tempdir().unwrap();
+        let args = test_args([
+            "--all-platforms",
+            "--all-environments",
+            "--ignore-pypi-errors",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+
+        let loads = || super::super::LOCK_FILE_LOADS.with(|loads| loads.get());
+        let before = loads();
+        execute(test_project(), args).await.unwrap();
+        assert_eq!(loads() - before, 1);
+
+        // Every combination of platform and environment is written
+        for environment in ["default", "with-pypi"] {
+            for platform in ["linux-64", "osx-arm64"] {
+                let target = temp_dir
+                    .path()
+                    .join(format!("conda-{platform}-{environment}.lock"));
+                let spec = ExplicitEnvironmentSpec::from_path(&target).unwrap();
+                assert_eq!(spec.platform.unwrap().as_str(), platform);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_only_pypi() {
+        let temp_dir = tempdir().unwrap();
+        let args = test_args([
+            "--environment",
+            "with-pypi",
+            "--platform",
+            "linux-64",
+            "--only-pypi",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        let requirements =
+            fs::read_to_string(temp_dir.path().join("requirements-linux-64-with-pypi.txt"))
+                .unwrap();
+        assert_eq!(requirements, SIX_REQUIREMENT);
+        assert!(!temp_dir
+            .path()
+            .join("conda-linux-64-with-pypi.lock")
+            .exists());
+
+        let parse = |extra: &[&str]| {
+            Args::try_parse_from(["conda-explicit-spec"].iter().chain(extra).copied())
+        };
+        assert!(parse(&["--only-pypi", "--ignore-pypi-errors"]).is_err());
+        assert!(parse(&["--only-pypi", "--only-conda"]).is_err());
+        assert!(parse(&["--only-conda", "--write-pypi-requirements"]).is_err());
+        assert!(parse(&["--only-conda"]).is_ok());
+    }
+
+    #[test]
+    fn test_pypi_mode() {
+        let apply = |extra: &[&str]| {
+            let mut args =
+                Args::try_parse_from(["conda-explicit-spec"].iter().chain(extra).copied())
+                    .map_err(|e| miette::miette!("{}", e))?;
+            args.apply_pypi_mode().map(|_| args)
+        };
+
+        let args = apply(&[]).unwrap();
+        assert_eq!(args.pypi_mode, Some(PypiMode::Bail));
+        let args = apply(&["--pypi-mode", "requirements", "--relative-paths"]).unwrap();
+        assert!(args.write_pypi_requirements && !args.ignore_pypi_errors);
+        let args = apply(&["--pypi-mode", "ignore"]).unwrap();
+        assert!(args.ignore_pypi_errors && !args.write_pypi_requirements);
+
+        // The deprecated flags are aliases of the modes
+        let args = apply(&["--write-pypi-requirements"]).unwrap();
+        assert_eq!(args.pypi_mode, Some(PypiMode::Requirements));
+        let args = apply(&["--ignore-pypi-errors"]).unwrap();
+        assert_eq!(args.pypi_mode, Some(PypiMode::Ignore));
+        let args = apply(&["--only-pypi"]).unwrap();
+        assert_eq!(args.pypi_mode, Some(PypiMode::Requirements));
+
+        assert!(apply(&["--pypi-mode", "ignore", "--write-pypi-requirements"]).is_err());
+        assert!(apply(&["--relative-paths"]).is_err());
+        let err = apply(&["--pypi-mode", "bail", "--relative-paths"]).unwrap_err();
+        assert_eq!(err.code().unwrap().to_string(), "conflicting-arguments");
+        assert!(err.to_string().contains("`--relative-paths`"), "{err}");
+        assert!(apply(&["--pypi-mode", "ignore", "--write-pypi-constraints"]).is_err());
+        assert!(apply(&["--pypi-mode", "requirements", "--only-conda"]).is_err());
+        // The requirements would be dropped silently since stdout only gets
+        // the explicit spec
+        let err = apply(&["--pypi-mode", "requirements", "--stdout"]).unwrap_err();
+        assert!(err.to_string().contains("`--stdout`"), "{err}");
+        assert!(apply(&["--pypi-mode", "ignore", "--only-pypi"]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export_split_noarch() {
+        let temp_dir = tempdir().unwrap();
+        let args = test_args([
+            "--all-platforms",
+            "--split-noarch",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        let urls = |file: &str| {
+            ExplicitEnvironmentSpec::from_path(&temp_dir.path().join(file))
+                .unwrap()
+                .packages
+                .into_iter()
+                .map(|p| p.url.path_segments().unwrap().last().unwrap().to_owned())
+                .collect::<Vec<_>>()
+        };
+
+        // The noarch packages are shared by all platforms and written once
+        assert_eq!(
+            urls("conda-noarch-default.lock"),
+            ["tzdata-2024a-h0c530f3_0.conda"]
+        );
+        assert_eq!(
+            urls("conda-linux-64-default.lock"),
+            [
+                "_libgcc_mutex-0.1-conda_forge.tar.bz2",
+                "bzip2-1.0.8-h4bc722e_7.conda",
+                "libzlib-1.3.1-h4ab18f5_1.conda",
+            ]
+        );
+        assert_eq!(
+            urls("conda-osx-arm64-default.lock"),
+            [
+                "bzip2-1.0.8-h99b78c6_7.conda",
+                "libzlib-1.3.1-hfb2fe0b_1.conda",
+            ]
+        );
+
+        assert!(
+            Args::try_parse_from(["conda-explicit-spec", "--split-noarch", "--stdout"]).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_pypi_inline() {
+        let temp_dir = tempdir().unwrap();
+        let args = test_args([
+            "--environment",
+            "with-pypi",
+            "--platform",
+            "linux-64",
+            "--pypi-inline",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        let target = temp_dir.path().join("conda-linux-64-with-pypi.lock");
+        let contents = fs::read_to_string(&target).unwrap();
+        let pypi = contents
+            .lines()
+            .filter(|line| line.starts_with("# pypi: "))
+            .collect_vec();
+        assert_eq!(pypi.len(), 1, "{contents}");
+        assert!(pypi[0].contains("six-1.16.0-py2.py3-none-any.whl"));
+        assert!(pypi[0].contains("--hash=sha256:"));
+        assert!(!temp_dir
+            .path()
+            .join("requirements-linux-64-with-pypi.txt")
+            .exists());
+
+        // The comments are ignored when the spec is parsed
+        let spec = ExplicitEnvironmentSpec::from_path(&target).unwrap();
+        assert_eq!(spec.packages.len(), 4);
+
+        assert!(Args::try_parse_from([
+            "conda-explicit-spec",
+            "--pypi-inline",
+            "--write-pypi-requirements"
+        ])
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_verify_package_urls() {
+        let temp_dir = tempdir().unwrap();
+        let existing = temp_dir.path().join("foo-1.0-h123_0.conda");
+        fs::write(&existing, "").unwrap();
+        let missing = temp_dir.path().join("bar-1.0-h123_0.conda");
+
+        let file = |contents: String| ExportedFile {
+            path: temp_dir.path().join("conda-linux-64-default.lock"),
+            platform: Some(Platform::Linux64),
+            environment: "default".to_string(),
+            packages: 1,
+            pypi: false,
+            contents,
+            sizes: BTreeMap::new(),
+        };
+        let url = |path: &Path| Url::from_file_path(path).unwrap();
+        let client = ClientWithMiddleware::from(reqwest::Client::new());
+        let timeout = Duration::from_secs(1);
+
+        let found = file(format!(
+            "# platform: linux-64\n@EXPLICIT\n{}#{MD5}\n",
+            url(&existing)
+        ));
+        assert_eq!(
+            exported_package_urls(std::slice::from_ref(&found)).unwrap(),
+            [url(&existing)]
+        );
+        verify_package_urls(&client, &[found], timeout, false)
+            .await
+            .unwrap();
+
+        let not_found = file(format!(
+            "@EXPLICIT\n{}\n{}\n",
+            url(&existing),
+            url(&missing)
+        ));
+        let err = verify_package_urls(&client, &[not_found], timeout, false)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("bar-1.0-h123_0.conda"), "{err}");
+        assert!(!err.to_string().contains("foo-1.0-h123_0.conda"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_export_no_install() {
+        // A project that was never installed, so there is no prefix on disk
+        let temp_dir = tempdir().unwrap();
+        let project_dir = temp_dir.path().join("project");
+        fs::create_dir(&project_dir).unwrap();
+        for file in ["pixi.toml", "pixi.lock"] {
+            fs::copy(test_project_dir().join(file), project_dir.join(file)).unwrap();
+        }
+        let project = Project::from_path(&project_dir.join("pixi.toml")).unwrap();
+        let prefix = project.default_environment().dir();
+
+        let output_dir = temp_dir.path().join("dist");
+        let args = Args::parse_from([
+            "conda-explicit-spec",
+            "--no-install",
+            "--platform",
+            "linux-64",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ]);
+        execute(project, args).await.unwrap();
+
+        assert!(output_dir.join("conda-linux-64-default.lock").is_file());
+        assert!(!prefix.exists());
+        assert!(!project_dir.join(consts::PIXI_DIR).exists());
+    }
+
+    #[tokio::test]
+    async fn test_export_dry_run() {
+        let temp_dir = tempdir().unwrap();
+        let output_dir = temp_dir.path().join("dist");
+
+        let args = test_args([
+            "--all-platforms",
+            "--environment",
+            "with-pypi",
+            "--write-pypi-requirements",
+            "--write-pypi-constraints",
+            "--dry-run",
+            "--output-dir",
+            output_dir.to_str().unwrap(),
+        ]);
+        assert!(execute(test_project(), args).await.unwrap().is_empty());
+
+        // Not even the output directory is created
+        assert!(!output_dir.exists());
+    }
+
+    #[test]
+    fn test_diff_locked_packages() {
+        let lock_file = test_lock_file();
+        let locked = |env| {
+            lock_file
+                .environment(env)
+                .unwrap()
+                .packages(Platform::Linux64)
+                .unwrap()
+                .collect_vec()
+        };
+        let baseline = locked("default")
+            .into_iter()
+            .filter(|p| p.name() != "bzip2")
+            .collect_vec();
+
+        let names = diff_locked_packages(locked("with-pypi"), &baseline)
+            .iter()
+            .map(|p| p.name().to_string())
+            .collect_vec();
+        assert_eq!(names, vec!["bzip2", "six"]);
+
+        // The baseline itself is empty after diffing
+        assert!(diff_locked_packages(baseline.clone(), &baseline).is_empty());
+    }
+
+    #[test]
+    fn test_pure_pypi_environment() {
+        // An environment with only PyPI packages has no conda packages to
+        // write to the explicit spec
+        let err =
+            should_write_explicit_spec(&test_args([]), "pypi", Platform::Linux64, 0).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("environment 'pypi' has no conda packages for linux-64"),
+            "{err}"
+        );
+
+        // Unless only the pypi files are exported
+        let args = test_args(["--write-pypi-requirements"]);
+        assert!(!should_write_explicit_spec(&args, "pypi", Platform::Linux64, 0).unwrap());
+        assert!(should_write_explicit_spec(&args, "pypi", Platform::Linux64, 1).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_export_report() {
+        let temp_dir = tempdir().unwrap();
+        let report = temp_dir.path().join("report.json");
+
+        let args = test_args([
+            "--platform",
+            "linux-64",
+            "--environment",
+            "with-pypi",
+            "--write-pypi-requirements",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+            "--report",
+            report.to_str().unwrap(),
+        ]);
+        execute(test_project(), args).await.unwrap();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(report).unwrap()).unwrap();
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "files": [
+                    {
+                        "path": temp_dir.path().join("conda-linux-64-with-pypi.lock"),
+                        "platform": "linux-64",
+                        "environment": "with-pypi",
+                        "packages": 4,
+                    },
+                    {
+                        "path": temp_dir.path().join("requirements-linux-64-with-pypi.txt"),
+                        "platform": "linux-64",
+                        "environment": "with-pypi",
+                        "packages": 1,
+                    },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_explicit_spec_channel_override() {
+        let package = conda_package_with_url(
+            &format!("https://conda.anaconda.org/conda-forge/linux-64/foo-1.0-h123_0.conda#sha256:{SHA256}"),
+            Some(MD5),
+            Some(SHA256),
+        );
+        let overrides = vec![
+            parse_channel_override("https://repo.anaconda.com/=https://mirror.internal/anaconda/")
+                .unwrap(),
+            parse_channel_override("https://conda.anaconda.org/=https://mirror.internal/conda/")
+                .unwrap(),
+        ];
+
+        let options = ExplicitSpecOptions {
+            channel_overrides: overrides,
+            ..ExplicitSpecOptions::default()
+        };
+        let ees = build_explicit_spec(Platform::Linux64, [&package], &options).unwrap();
+        assert_eq!(
+            ees.packages[0].url.as_str(),
+            format!("https://mirror.internal/conda/conda-forge/linux-64/foo-1.0-h123_0.conda#sha256:{SHA256}")
+        );
+
+        assert!(parse_channel_override("https://conda.anaconda.org/").is_err());
+        assert!(parse_channel_override("=https://mirror.internal/conda/").is_err());
+        assert!(parse_channel_override("https://conda.anaconda.org/=mirror").is_err());
+    }
+}
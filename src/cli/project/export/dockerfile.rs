@@ -0,0 +1,189 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::conda_explicit_spec::{
+    cwd, render_explicit_spec, ExplicitSpecOptions, GENERATED_BY_HEADER,
+};
+use crate::cli::cli_config::PrefixUpdateConfig;
+use crate::Project;
+use clap::Parser;
+use rattler_conda_types::Platform;
+
+/// The tool that installs the explicit spec in the container
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Installer {
+    #[default]
+    Micromamba,
+    Conda,
+}
+
+impl Installer {
+    fn command(self) -> &'static str {
+        match self {
+            Installer::Micromamba => "micromamba",
+            Installer::Conda => "conda",
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    /// The directory to write the `Dockerfile-{platform}-{environment}`
+    /// snippet and the explicit spec it installs to. Defaults to the current
+    /// working directory.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// The platform of the container.
+    #[arg(long, default_value = "linux-64")]
+    pub platform: Platform,
+
+    /// The environment to render. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    /// The tool that creates the environment in the container.
+    #[arg(long, default_value = "micromamba", value_enum)]
+    pub installer: Installer,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// Renders the Dockerfile instructions that copy the explicit spec into the
+/// container and create the environment from it.
+fn render_dockerfile(installer: Installer, spec_file: &str, environment_name: &str) -> String {
+    let command = installer.command();
+    format!(
+        "# Generated by `pixi project export dockerfile`\n\
+        COPY {spec_file} /tmp/{spec_file}\n\
+        RUN {command} create --yes --name {environment_name} --file /tmp/{spec_file} \\\n    \
+        && {command} clean --all --yes \\\n    \
+        && rm /tmp/{spec_file}\n"
+    )
+}
+
+pub async fn execute(project: Project, args: Args) -> miette::Result<()> {
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+    let options = ExplicitSpecOptions {
+        lock_file_usage: args.prefix_update_config.lock_file_usage(),
+        no_install: args.prefix_update_config.no_install(),
+        ..ExplicitSpecOptions::default()
+    };
+    let ees = project
+        .export_conda_explicit_spec(&environment, args.platform, options)
+        .await?;
+
+    let output_dir = match args.output_dir {
+        Some(output_dir) => output_dir,
+        None => cwd()?,
+    };
+    let environment_name = environment.name().as_str();
+    let spec_file = format!("conda-{}-{}.lock", args.platform, environment_name);
+    let spec_target = output_dir.join(&spec_file);
+
+    tracing::info!("Creating conda lock file");
+    fs::write(
+        &spec_target,
+        render_explicit_spec(&ees, GENERATED_BY_HEADER),
+    )
+    .map_err(|e| {
+        miette::miette!(
+            "Could not write conda lock file '{}': {}",
+            spec_target.display(),
+            e
+        )
+    })?;
+
+    let target = output_dir.join(format!("Dockerfile-{}-{}", args.platform, environment_name));
+    tracing::info!("Creating Dockerfile snippet");
+    fs::write(
+        &target,
+        render_dockerfile(args.installer, &spec_file, environment_name),
+    )
+    .map_err(|e| {
+        miette::miette!(
+            "Could not write Dockerfile snippet '{}': {}",
+            target.display(),
+            e
+        )
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_render_dockerfile() {
+        assert_eq!(
+            render_dockerfile(
+                Installer::Micromamba,
+                "conda-linux-64-default.lock",
+                "default"
+            ),
+            "# Generated by `pixi project export dockerfile`\n\
+            COPY conda-linux-64-default.lock /tmp/conda-linux-64-default.lock\n\
+            RUN micromamba create --yes --name default --file /tmp/conda-linux-64-default.lock \\\n    \
+            && micromamba clean --all --yes \\\n    \
+            && rm /tmp/conda-linux-64-default.lock\n"
+        );
+        assert_eq!(
+            render_dockerfile(Installer::Conda, "env.lock", "cuda"),
+            "# Generated by `pixi project export dockerfile`\n\
+            COPY env.lock /tmp/env.lock\n\
+            RUN conda create --yes --name cuda --file /tmp/env.lock \\\n    \
+            && conda clean --all --yes \\\n    \
+            && rm /tmp/env.lock\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_dockerfile() {
+        let project_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/cli/project/export/test-data/testenv");
+        for installer in ["micromamba", "conda"] {
+            let project = Project::from_path(&project_dir.join("pixi.toml")).unwrap();
+            let temp_dir = tempdir().unwrap();
+            let args = Args::parse_from([
+                "dockerfile",
+                "--no-lockfile-update",
+                "--installer",
+                installer,
+                "--output-dir",
+                temp_dir.path().to_str().unwrap(),
+            ]);
+            execute(project, args).await.unwrap();
+
+            let spec =
+                fs::read_to_string(temp_dir.path().join("conda-linux-64-default.lock")).unwrap();
+            assert_eq!(
+                spec,
+                "# Generated by `pixi project export`\n\
+                # platform: linux-64\n\
+                @EXPLICIT\n\
+                https://conda.anaconda.org/conda-forge/linux-64/_libgcc_mutex-0.1-conda_forge.tar.bz2#d7c89558ba9fa0495403155b64376d81\n\
+                https://conda.anaconda.org/conda-forge/linux-64/bzip2-1.0.8-h4bc722e_7.conda#62ee74e96c5ebb0af99386de58cf9553\n\
+                https://conda.anaconda.org/conda-forge/linux-64/libzlib-1.3.1-h4ab18f5_1.conda#57d7dc60e9325e3de37ff8dffd18e814\n\
+                https://conda.anaconda.org/conda-forge/noarch/tzdata-2024a-h0c530f3_0.conda#161081fc7cec0bfda0d86d7cb595f8d8\n"
+            );
+            let dockerfile =
+                fs::read_to_string(temp_dir.path().join("Dockerfile-linux-64-default")).unwrap();
+            assert_eq!(
+                dockerfile,
+                format!(
+                    "# Generated by `pixi project export dockerfile`\n\
+                    COPY conda-linux-64-default.lock /tmp/conda-linux-64-default.lock\n\
+                    RUN {installer} create --yes --name default --file /tmp/conda-linux-64-default.lock \\\n    \
+                    && {installer} clean --all --yes \\\n    \
+                    && rm /tmp/conda-linux-64-default.lock\n"
+                )
+            );
+        }
+    }
+}
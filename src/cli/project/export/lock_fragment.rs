@@ -0,0 +1,154 @@
+use std::path::PathBuf;
+
+use super::conda_explicit_spec::{cwd, locked_platform_packages};
+use crate::cli::cli_config::PrefixUpdateConfig;
+use crate::Project;
+use clap::Parser;
+use miette::{Context, IntoDiagnostic};
+use rattler_conda_types::Platform;
+use rattler_lock::{LockFile, LockFileBuilder};
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    /// The directory to write the `pixi-{platform}-{environment}.lock` file
+    /// to. Defaults to the current working directory.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// The platform to render. Defaults to the only platform of the
+    /// environment in the lock file, or the current platform.
+    #[arg(long)]
+    pub platform: Option<Platform>,
+
+    /// The environment to render. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// Builds a lock file that only contains the conda and pypi packages of a
+/// single environment and platform of the given lock file, together with the
+/// channels and pypi indexes of that environment.
+pub(super) fn build_lock_file_fragment(
+    lock_file: &LockFile,
+    environment_name: &str,
+    platform: Platform,
+) -> miette::Result<LockFile> {
+    let environment = lock_file.environment(environment_name).ok_or_else(|| {
+        miette::miette!(
+            "environment '{}' not found in the lock file",
+            environment_name
+        )
+    })?;
+    let packages = environment.packages(platform).ok_or_else(|| {
+        miette::miette!(
+            "platform '{}' not found in the lock file for environment '{}'",
+            platform,
+            environment_name
+        )
+    })?;
+
+    let mut builder = LockFileBuilder::new();
+    builder.set_channels(environment_name, environment.channels().to_vec());
+    if let Some(indexes) = environment.pypi_indexes() {
+        builder.set_pypi_indexes(environment_name, indexes.clone());
+    }
+    for package in packages {
+        builder.add_package(environment_name, platform, package);
+    }
+
+    Ok(builder.finish())
+}
+
+pub async fn execute(project: Project, args: Args) -> miette::Result<()> {
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+    let lock_file = super::load_lock_file(
+        &project,
+        args.prefix_update_config.lock_file_usage(),
+        args.prefix_update_config.no_install(),
+        0,
+    )
+    .await?;
+
+    let platform = args
+        .platform
+        .unwrap_or_else(|| super::default_platform(&lock_file, &environment));
+    // Fails with the supported and locked platforms of the environment
+    // before the fragment is built
+    locked_platform_packages(&lock_file, &environment, platform)?;
+    let fragment = build_lock_file_fragment(&lock_file, environment.name().as_str(), platform)?;
+
+    let output_dir = match args.output_dir {
+        Some(output_dir) => output_dir,
+        None => cwd()?,
+    };
+    let target = output_dir.join(format!(
+        "pixi-{}-{}.lock",
+        platform,
+        environment.name().as_str()
+    ));
+
+    tracing::info!("Creating lock file fragment");
+    fragment
+        .to_path(&target)
+        .into_diagnostic()
+        .with_context(|| format!("Could not write lock file '{}'", target.display()))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_build_lock_file_fragment() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/cli/project/export/test-data/testenv/pixi.lock");
+        let lock_file = LockFile::from_path(&path).unwrap();
+
+        let fragment =
+            build_lock_file_fragment(&lock_file, "with-pypi", Platform::Linux64).unwrap();
+        let temp_dir = tempdir().unwrap();
+        let target = temp_dir.path().join("pixi-linux-64-with-pypi.lock");
+        fragment.to_path(&target).unwrap();
+
+        // The fragment round trips and only contains the selected environment
+        // and platform
+        let parsed = LockFile::from_path(&target).unwrap();
+        assert_eq!(
+            parsed
+                .environments()
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>(),
+            ["with-pypi"]
+        );
+        let environment = parsed.environment("with-pypi").unwrap();
+        assert_eq!(
+            environment.platforms().collect::<Vec<_>>(),
+            [Platform::Linux64]
+        );
+
+        let names = |lock_file: &LockFile| {
+            let mut names = lock_file
+                .environment("with-pypi")
+                .unwrap()
+                .packages(Platform::Linux64)
+                .unwrap()
+                .map(|p| p.name().to_string())
+                .collect::<Vec<_>>();
+            names.sort();
+            names
+        };
+        assert_eq!(names(&parsed), names(&lock_file));
+        assert!(names(&parsed).contains(&"six".to_string()));
+
+        assert!(build_lock_file_fragment(&lock_file, "with-pypi", Platform::Win64).is_err());
+        assert!(build_lock_file_fragment(&lock_file, "missing", Platform::Linux64).is_err());
+    }
+}
@@ -0,0 +1,148 @@
+use super::conda_explicit_spec::{channel_of_entry, export_match_spec_file, MatchSpecArgs};
+use crate::Project;
+use rattler_conda_types::ChannelConfig;
+use rattler_lock::CondaPackage;
+use url::Url;
+
+/// The arguments of `match-spec`, which writes a
+/// `match-spec-{platform}-{environment}.txt` file.
+pub type Args = MatchSpecArgs;
+
+/// Returns the channel of a conda package as its canonical name, e.g.
+/// `conda-forge` for a package from the channel alias and the url of the
+/// channel otherwise.
+fn channel_name(package: &CondaPackage, channel_config: &ChannelConfig) -> String {
+    let channel = channel_of_entry(package.url().as_str());
+    match Url::parse(&format!("{}/", channel)) {
+        Ok(base_url) => channel_config
+            .canonical_name(&base_url)
+            .trim_end_matches('/')
+            .to_string(),
+        Err(_) => channel.to_string(),
+    }
+}
+
+/// Renders the conda packages as `channel::name=version=build` match specs,
+/// which stay valid when the packages move to another url of their channel.
+fn render_match_spec(conda_packages: &[CondaPackage], channel_config: &ChannelConfig) -> String {
+    let mut spec = String::from("# Generated by `pixi project export match-spec`\n");
+    for package in conda_packages {
+        let record = package.package_record();
+        spec.push_str(&format!(
+            "{}::{}={}={}\n",
+            channel_name(package, channel_config),
+            record.name.as_normalized(),
+            record.version,
+            record.build
+        ));
+    }
+    spec
+}
+
+pub async fn execute(project: Project, args: Args) -> miette::Result<()> {
+    let channel_config = project.channel_config();
+    export_match_spec_file(
+        &project,
+        args,
+        "match-spec",
+        "match spec",
+        |conda_packages| render_match_spec(conda_packages, &channel_config),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::project::export::test_utils::SIX_REQUIREMENT;
+    use clap::Parser;
+    use rattler_conda_types::Platform;
+    use rattler_lock::LockFile;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use tempfile::tempdir;
+
+    /// The match spec of the linux-64 packages of the test project.
+    const LINUX_64_SPEC: &str = "# Generated by `pixi project export match-spec`\n\
+        conda-forge::_libgcc_mutex=0.1=conda_forge\n\
+        conda-forge::bzip2=1.0.8=h4bc722e_7\n\
+        conda-forge::libzlib=1.3.1=h4ab18f5_1\n\
+        conda-forge::tzdata=2024a=h0c530f3_0\n";
+
+    fn test_project_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/cli/project/export/test-data/testenv")
+    }
+
+    #[test]
+    fn test_render_match_spec() {
+        let lock_file = LockFile::from_path(&test_project_dir().join("pixi.lock")).unwrap();
+        let packages = lock_file
+            .environment("default")
+            .unwrap()
+            .packages(Platform::Linux64)
+            .unwrap()
+            .filter_map(|p| p.as_conda().cloned())
+            .collect::<Vec<_>>();
+        let channel_config = ChannelConfig::default_with_root_dir(test_project_dir());
+
+        assert_eq!(render_match_spec(&packages, &channel_config), LINUX_64_SPEC);
+
+        // Channels that are not below the channel alias keep their url
+        let mirror = ChannelConfig {
+            channel_alias: Url::parse("https://mirror.internal/").unwrap(),
+            ..channel_config
+        };
+        assert_eq!(
+            channel_name(&packages[0], &mirror),
+            "https://conda.anaconda.org/conda-forge"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_match_spec() {
+        let project = || Project::from_path(&test_project_dir().join("pixi.toml")).unwrap();
+        let temp_dir = tempdir().unwrap();
+        let args = Args::parse_from([
+            "match-spec",
+            "--no-lockfile-update",
+            "--environment",
+            "with-pypi",
+            "--platform",
+            "linux-64",
+            "--pypi-mode",
+            "requirements",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(project(), args).await.unwrap();
+
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("match-spec-linux-64-with-pypi.txt")).unwrap(),
+            LINUX_64_SPEC
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("requirements-linux-64-with-pypi.txt"))
+                .unwrap(),
+            SIX_REQUIREMENT
+        );
+
+        // An explicit lock file is exported as-is
+        let args = Args::parse_from([
+            "match-spec",
+            "--lockfile",
+            test_project_dir().join("pixi.lock").to_str().unwrap(),
+            "--platform",
+            "osx-arm64",
+            "--output-dir",
+            temp_dir.path().to_str().unwrap(),
+        ]);
+        execute(project(), args).await.unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("match-spec-osx-arm64-default.txt")).unwrap(),
+            "# Generated by `pixi project export match-spec`\n\
+             conda-forge::bzip2=1.0.8=h99b78c6_7\n\
+             conda-forge::libzlib=1.3.1=hfb2fe0b_1\n\
+             conda-forge::tzdata=2024a=h0c530f3_0\n"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+use super::conda_explicit_spec::{export_match_spec_file, MatchSpecArgs};
+use crate::Project;
+use rattler_lock::CondaPackage;
+
+/// The arguments of `micromamba-spec`, which writes a
+/// `micromamba-{platform}-{environment}.txt` file.
+pub type Args = MatchSpecArgs;
+
+/// Renders the conda packages as match specs pinned to their exact
+/// `name=version=build`, which can be installed with `micromamba install
+/// --file`. The channels are not included, so the file can be installed from
+/// any channel or mirror that has the same builds.
+fn render_micromamba_spec(conda_packages: &[CondaPackage]) -> String {
+    let mut spec = String::from("# Generated by `pixi project export micromamba-spec`\n");
+    for package in conda_packages {
+        let record = package.package_record();
+        spec.push_str(&format!(
+            "{}={}={}\n",
+            record.name.as_normalized(),
+            record.version,
+            record.build
+        ));
+    }
+    spec
+}
+
+pub async fn execute(project: Project, args: Args) -> miette::Result<()> {
+    export_match_spec_file(
+        &project,
+        args,
+        "micromamba",
+        "micromamba spec",
+        render_micromamba_spec,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::project::export::test_utils::SIX_REQUIREMENT;
+    use clap::Parser;
+    use rattler_conda_types::Platform;
+    use rattler_lock::LockFile;
+    use std::fs;
+    use std::path::Path;
+    use tempfile::tempdir;
+
+    /// The micromamba spec of the linux-64 packages of the test project.
+    const LINUX_64_SPEC: &str = "# Generated by `pixi project export micromamba-spec`\n\
+        _libgcc_mutex=0.1=conda_forge\n\
+        bzip2=1.0.8=h4bc722e_7\n\
+        libzlib=1.3.1=h4ab18f5_1\n\
+        tzdata=2024a=h0c530f3_0\n";
+
+    #[test]
+    fn test_render_micromamba_spec() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/cli/project/export/test-data/testenv/pixi.lock");
+        let lock_file = LockFile::from_path(&path).unwrap();
+        let packages = lock_file
+            .environment("default")
+            .unwrap()
+            .packages(Platform::Linux64)
+            .unwrap()
+            .filter_map(|p| p.as_conda().cloned())
+            .collect::<Vec<_>>();
+
+        assert_eq!(render_micromamba_spec(&packages), LINUX_64_SPEC);
+    }
+
+    #[tokio::test]
+    async fn test_export_micromamba_spec_pypi() {
+        let project_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/cli/project/export/test-data/testenv");
+        let project = || Project::from_path(&project_dir.join("pixi.toml")).unwrap();
+        let temp_dir = tempdir().unwrap();
+        let args = |extra: &[&str]| {
+            Args::parse_from(
+                [
+                    "micromamba-spec",
+                    "--no-lockfile-update",
+                    "--environment",
+                    "with-pypi",
+                    "--platform",
+                    "linux-64",
+                    "--output-dir",
+                    temp_dir.path().to_str().unwrap(),
+                ]
+                .iter()
+                .chain(extra),
+            )
+        };
+
+        // PyPI packages are rejected like in the conda explicit spec
+        assert!(execute(project(), args(&[])).await.is_err());
+
+        execute(project(), args(&["--pypi-mode", "ignore"]))
+            .await
+            .unwrap();
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("micromamba-linux-64-with-pypi.txt")).unwrap(),
+            LINUX_64_SPEC
+        );
+        assert!(!temp_dir
+            .path()
+            .join("requirements-linux-64-with-pypi.txt")
+            .exists());
+
+        execute(project(), args(&["--pypi-mode", "requirements"]))
+            .await
+            .unwrap();
+        let requirements =
+            fs::read_to_string(temp_dir.path().join("requirements-linux-64-with-pypi.txt"))
+                .unwrap();
+        assert_eq!(requirements, SIX_REQUIREMENT);
+    }
+}
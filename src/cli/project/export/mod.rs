@@ -0,0 +1,297 @@
+pub mod conda_environment;
+pub mod conda_explicit_spec;
+pub mod dockerfile;
+pub mod lock_fragment;
+pub mod match_spec;
+pub mod micromamba_spec;
+pub mod pylock;
+pub mod spack;
+#[cfg(test)]
+mod test_utils;
+
+use crate::environment::LockFileUsage;
+use crate::lock_file::UpdateLockFileOptions;
+use crate::project::Environment;
+use crate::Project;
+use clap::Parser;
+use pixi_consts::consts;
+use rattler_conda_types::Platform;
+use rattler_lock::LockFile;
+use serde::Deserialize;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Commands to export projects to other formats
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The path to 'pixi.toml' or 'pyproject.toml'
+    #[clap(long, global = true)]
+    pub manifest_path: Option<PathBuf>,
+
+    /// The subcommand to execute
+    #[clap(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Parser, Debug)]
+pub enum Command {
+    /// Export project environment to a conda explicit specification file
+    ///
+    /// By default the lock file is updated first if it is out of date. With
+    /// `--frozen` (or `--no-lockfile-update`) the existing lock file is used
+    /// as-is, and with `--locked` the export aborts if the lock file is out of
+    /// date. In both cases the lock file is never modified and the export
+    /// fails if it doesn't exist.
+    ///
+    /// The export only reads the lock file and never installs the
+    /// environment. With `--no-install` an out of date lock file is still
+    /// updated, but no prefix is created on disk, which fails if updating the
+    /// PyPI dependencies requires one.
+    ///
+    /// A failed export exits with 2 if the environment has PyPI packages that
+    /// are not exported, 3 if the platform is not supported or locked, 4 if
+    /// the environment doesn't exist or is not locked, and 1 otherwise.
+    #[clap(visible_alias = "ces")]
+    CondaExplicitSpec(conda_explicit_spec::Args),
+    /// Export project environment to a conda environment.yml file
+    CondaEnvironment(conda_environment::Args),
+    /// Export project environment to a Dockerfile snippet that creates it
+    /// from a conda explicit specification file
+    Dockerfile(dockerfile::Args),
+    /// Export project environment to a file of `channel::name=version=build`
+    /// match specs
+    MatchSpec(match_spec::Args),
+    /// Export project environment to a micromamba `--file` spec with the
+    /// conda packages pinned to their `name=version=build`
+    MicromambaSpec(micromamba_spec::Args),
+    /// Export project environment to a spack.yaml style list of specs
+    Spack(spack::Args),
+    /// Export the locked packages of a single environment and platform as a
+    /// minimal lock file that can be read by other tools using rattler-lock
+    RattlerLock(lock_fragment::Args),
+    /// Export the PyPI packages of project environment to a PEP 751
+    /// pylock.toml file
+    Pylock(pylock::Args),
+}
+
+#[cfg(test)]
+thread_local! {
+    /// The number of times [`load_lock_file`] was called on this thread, the
+    /// export tests use it to check that the lock file is only loaded once.
+    static LOCK_FILE_LOADS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Loads the lock file of the project that all exporters operate on,
+/// updating it first if it is out of date and that is allowed. Errors if the
+/// lock file is missing and it may not be created. Updates that fail because
+/// of a transient network error are retried up to `max_retries` times with an
+/// exponential backoff.
+async fn load_lock_file(
+    project: &Project,
+    lock_file_usage: LockFileUsage,
+    no_install: bool,
+    max_retries: u32,
+) -> miette::Result<LockFile> {
+    #[cfg(test)]
+    LOCK_FILE_LOADS.with(|loads| loads.set(loads.get() + 1));
+
+    let lock_file_path = project.lock_file_path();
+    if !lock_file_usage.allows_lock_file_updates() && !lock_file_path.is_file() {
+        miette::bail!(
+            code = "lock-file-missing",
+            "lock-file '{}' does not exist, run `pixi install` to create it",
+            lock_file_path.display()
+        );
+    }
+
+    let mut attempt = 0;
+    loop {
+        let result = project
+            .update_lock_file(UpdateLockFileOptions {
+                lock_file_usage,
+                no_install,
+                ..UpdateLockFileOptions::default()
+            })
+            .await;
+        match result {
+            Ok(derived) => return Ok(derived.lock_file),
+            Err(err) if attempt < max_retries && is_transient_network_error(&err) => {
+                let backoff = retry_backoff(attempt);
+                attempt += 1;
+                tracing::info!(
+                    "updating the lock-file failed with a network error, retrying in {}s ({}/{}): {}",
+                    backoff.as_secs(),
+                    attempt,
+                    max_retries,
+                    err
+                );
+                tokio::time::sleep(backoff).await;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+/// Returns the time to wait before the retry after the given attempt, doubling
+/// from one second.
+fn retry_backoff(attempt: u32) -> Duration {
+    Duration::from_secs(1 << attempt.min(6))
+}
+
+/// Returns true if the error is caused by a network error that may succeed
+/// when retried, like a timeout or a dropped connection.
+fn is_transient_network_error(err: &miette::Report) -> bool {
+    err.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<reqwest::Error>() {
+            return err.is_timeout()
+                || err.is_connect()
+                || err
+                    .status()
+                    .is_some_and(|status| status.is_server_error() || status.as_u16() == 429);
+        }
+        if let Some(reqwest_middleware::Error::Reqwest(err)) =
+            cause.downcast_ref::<reqwest_middleware::Error>()
+        {
+            return err.is_timeout() || err.is_connect();
+        }
+        cause.downcast_ref::<std::io::Error>().is_some_and(|err| {
+            matches!(
+                err.kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::ConnectionReset
+                    | std::io::ErrorKind::ConnectionAborted
+                    | std::io::ErrorKind::ConnectionRefused
+                    | std::io::ErrorKind::NotConnected
+            )
+        })
+    })
+}
+
+/// Returns the platform to export when no platform is given. If the lock
+/// file only contains a single platform for the environment that platform is
+/// used, otherwise the best platform for the current system.
+fn default_platform(lock_file: &LockFile, environment: &Environment<'_>) -> Platform {
+    let locked_platforms = lock_file
+        .environment(environment.name().as_str())
+        .map(|env| env.platforms().collect::<Vec<_>>())
+        .unwrap_or_default();
+    match locked_platforms.as_slice() {
+        [platform] => {
+            tracing::debug!(
+                "using '{}', the only platform of environment '{}' in the lock file",
+                platform,
+                environment.name()
+            );
+            *platform
+        }
+        _ => environment.best_platform(),
+    }
+}
+
+/// Defaults for the exports that are read from [`consts::EXPORT_CONFIG_FILE`]
+/// in the project root. The command line arguments take precedence.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+struct ExportConfig {
+    environment: Option<String>,
+    platform: Option<Platform>,
+    #[serde(default)]
+    all_platforms: bool,
+    /// Relative to the project root.
+    output_dir: Option<PathBuf>,
+}
+
+/// Loads the export config of the project, all defaults are empty if the
+/// project doesn't have one.
+fn load_export_config(project: &Project) -> miette::Result<ExportConfig> {
+    let path = project.root().join(consts::EXPORT_CONFIG_FILE);
+    if !path.is_file() {
+        return Ok(ExportConfig::default());
+    }
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| miette::miette!("Could not read '{}': {}", path.display(), e))?;
+    let mut config: ExportConfig = toml_edit::de::from_str(&contents)
+        .map_err(|e| miette::miette!("Could not parse '{}': {}", path.display(), e))?;
+    config.output_dir = config.output_dir.map(|dir| project.root().join(dir));
+    tracing::debug!("using the export defaults from '{}'", path.display());
+    Ok(config)
+}
+
+/// Validates a name that is used in the exported file names, it can't
+/// contain path separators.
+fn parse_export_name(s: &str) -> Result<String, String> {
+    if s.is_empty() {
+        return Err("the name can't be empty".to_string());
+    }
+    if s.contains(['/', '\\']) {
+        return Err(format!(
+            "the name can't contain path separators, got `{}`",
+            s
+        ));
+    }
+    Ok(s.to_string())
+}
+
+pub async fn execute(args: Args) -> miette::Result<()> {
+    let project = Project::load_or_else_discover(args.manifest_path.as_deref())?;
+
+    match args.command {
+        Command::CondaExplicitSpec(args) => conda_explicit_spec::execute(project, args)
+            .await
+            .map(|_| ()),
+        Command::CondaEnvironment(args) => conda_environment::execute(project, args).await,
+        Command::Dockerfile(args) => dockerfile::execute(project, args).await,
+        Command::MatchSpec(args) => match_spec::execute(project, args).await,
+        Command::MicromambaSpec(args) => micromamba_spec::execute(project, args).await,
+        Command::Spack(args) => spack::execute(project, args).await,
+        Command::RattlerLock(args) => lock_fragment::execute(project, args).await,
+        Command::Pylock(args) => pylock::execute(project, args).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Error, ErrorKind};
+    use std::path::Path;
+
+    #[test]
+    fn test_default_platform() {
+        let project_dir =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("src/cli/project/export/test-data/testenv");
+        let project = Project::from_path(&project_dir.join("pixi.toml")).unwrap();
+        let environment = project.default_environment();
+        let lock_file = LockFile::from_path(&project_dir.join("pixi.lock")).unwrap();
+
+        // With multiple locked platforms the best platform is used
+        assert_eq!(
+            default_platform(&lock_file, &environment),
+            environment.best_platform()
+        );
+
+        // With a single locked platform that platform is used, even if it is
+        // not the current platform
+        for platform in [Platform::Linux64, Platform::OsxArm64] {
+            let single =
+                lock_fragment::build_lock_file_fragment(&lock_file, "default", platform).unwrap();
+            assert_eq!(default_platform(&single, &environment), platform);
+        }
+    }
+
+    #[test]
+    fn test_is_transient_network_error() {
+        let timeout = miette::Report::from_err(Error::new(ErrorKind::TimedOut, "timed out"));
+        assert!(is_transient_network_error(&timeout));
+
+        let unsolvable = miette::miette!("cannot solve the request");
+        assert!(!is_transient_network_error(&unsolvable));
+        let not_found = miette::Report::from_err(Error::new(ErrorKind::NotFound, "not found"));
+        assert!(!is_transient_network_error(&not_found));
+
+        assert_eq!(retry_backoff(0), Duration::from_secs(1));
+        assert_eq!(retry_backoff(3), Duration::from_secs(8));
+        assert_eq!(retry_backoff(20), Duration::from_secs(64));
+    }
+}
@@ -0,0 +1,293 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::conda_explicit_spec::{cwd, locked_platform_packages};
+use crate::cli::cli_config::PrefixUpdateConfig;
+use crate::Project;
+use clap::Parser;
+use rattler_conda_types::Platform;
+use rattler_lock::{PackageHashes, PypiPackageData, UrlOrPath};
+use toml_edit::{value, ArrayOfTables, DocumentMut, InlineTable, Item, Table};
+use url::Url;
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    /// The directory to write the `pylock.{platform}-{environment}.toml` file
+    /// to. Defaults to the current working directory.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// The platform to render. Defaults to the only platform of the
+    /// environment in the lock file, or the current platform.
+    #[arg(long)]
+    pub platform: Option<Platform>,
+
+    /// The environment to render. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// Returns the hashes of a package as the `hashes` table of a `pylock.toml`
+/// file. The hashes are required for all packages that are not a directory.
+fn hashes_table(package: &PypiPackageData) -> miette::Result<InlineTable> {
+    let mut hashes = InlineTable::new();
+    match &package.hash {
+        Some(PackageHashes::Md5(md5)) => {
+            hashes.insert("md5", format!("{:x}", md5).into());
+        }
+        Some(PackageHashes::Sha256(sha256)) => {
+            hashes.insert("sha256", format!("{:x}", sha256).into());
+        }
+        Some(PackageHashes::Md5Sha256(md5, sha256)) => {
+            hashes.insert("md5", format!("{:x}", md5).into());
+            hashes.insert("sha256", format!("{:x}", sha256).into());
+        }
+        None => miette::bail!(
+            "PyPI package {} has no hash, which is required in a pylock.toml file",
+            package.name
+        ),
+    }
+    Ok(hashes)
+}
+
+/// Returns the table describing where a package is installed from, together
+/// with its key in the package table, e.g. `wheels` or `sdist`.
+fn package_source(package: &PypiPackageData) -> miette::Result<(&'static str, Item)> {
+    let url = match &package.url_or_path {
+        UrlOrPath::Path(path) => {
+            let path = path.to_string_lossy().replace('\\', "/");
+            let is_archive = [".whl", ".tar.gz", ".zip"]
+                .iter()
+                .any(|ext| path.ends_with(ext));
+            let mut table = Table::new();
+            table.insert("path", value(path));
+            return if is_archive && !package.editable {
+                table.insert("hashes", value(hashes_table(package)?));
+                Ok(("archive", Item::Table(table)))
+            } else {
+                table.insert("editable", value(package.editable));
+                Ok(("directory", Item::Table(table)))
+            };
+        }
+        UrlOrPath::Url(url) => url,
+    };
+
+    // The `direct+` prefix is specific to the lock file
+    let url = match url.as_str().strip_prefix("direct+") {
+        Some(stripped) => Url::parse(stripped)
+            .map_err(|e| miette::miette!("invalid url of PyPI package {}: {}", package.name, e))?,
+        None => url.clone(),
+    };
+
+    if let Some(scheme) = url.scheme().strip_prefix("git+") {
+        let commit_id = url.fragment().ok_or_else(|| {
+            miette::miette!(
+                "the git url of PyPI package {} is not pinned to a commit",
+                package.name
+            )
+        })?;
+        let mut repository = url.clone();
+        repository.set_fragment(None);
+        repository.set_query(None);
+        let repository = format!(
+            "{}{}",
+            scheme,
+            &repository.as_str()[repository.scheme().len()..]
+        );
+
+        let mut table = Table::new();
+        table.insert("type", value("git"));
+        table.insert("url", value(repository));
+        table.insert("commit-id", value(commit_id));
+        return Ok(("vcs", Item::Table(table)));
+    }
+
+    let file_name = url
+        .path_segments()
+        .and_then(|segments| segments.last())
+        .unwrap_or_default()
+        .to_string();
+    let mut table = Table::new();
+    table.insert("name", value(file_name.as_str()));
+    table.insert("url", value(url.as_str()));
+    table.insert("hashes", value(hashes_table(package)?));
+
+    if file_name.ends_with(".whl") {
+        let mut wheels = ArrayOfTables::new();
+        wheels.push(table);
+        Ok(("wheels", Item::ArrayOfTables(wheels)))
+    } else {
+        Ok(("sdist", Item::Table(table)))
+    }
+}
+
+/// Renders the locked pypi packages as a PEP 751 `pylock.toml` file. The
+/// packages are sorted by name as recommended by the specification.
+fn render_pylock(packages: &[PypiPackageData]) -> miette::Result<String> {
+    let mut document = DocumentMut::new();
+    document.insert("lock-version", value("1.0"));
+    document.insert("created-by", value("pixi"));
+
+    let mut tables = ArrayOfTables::new();
+    let mut packages = packages.iter().collect::<Vec<_>>();
+    packages.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.version.cmp(&b.version)));
+    for package in packages {
+        let mut table = Table::new();
+        table.insert("name", value(package.name.to_string()));
+        table.insert("version", value(package.version.to_string()));
+        if let Some(requires_python) = &package.requires_python {
+            if !requires_python.is_empty() {
+                table.insert("requires-python", value(requires_python.to_string()));
+            }
+        }
+        let (key, source) = package_source(package)?;
+        table.insert(key, source);
+        tables.push(table);
+    }
+    document.insert("packages", Item::ArrayOfTables(tables));
+
+    Ok(format!(
+        "# Generated by `pixi project export pylock`\n{}",
+        document
+    ))
+}
+
+pub async fn execute(project: Project, args: Args) -> miette::Result<()> {
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+    let lock_file = super::load_lock_file(
+        &project,
+        args.prefix_update_config.lock_file_usage(),
+        args.prefix_update_config.no_install(),
+        0,
+    )
+    .await?;
+
+    let platform = args
+        .platform
+        .unwrap_or_else(|| super::default_platform(&lock_file, &environment));
+    let locked_deps = locked_platform_packages(&lock_file, &environment, platform)?;
+
+    let pypi_packages = locked_deps
+        .into_iter()
+        .filter_map(|p| p.as_pypi().map(|p| p.data().package.clone()))
+        .collect::<Vec<_>>();
+    if pypi_packages.is_empty() {
+        tracing::warn!(
+            "environment '{}' has no PyPI packages for platform '{}'",
+            environment.name(),
+            platform
+        );
+    }
+    let contents = render_pylock(&pypi_packages)?;
+
+    let output_dir = match args.output_dir {
+        Some(output_dir) => output_dir,
+        None => cwd()?,
+    };
+    let target = output_dir.join(format!(
+        "pylock.{}-{}.toml",
+        platform,
+        environment.name().as_str()
+    ));
+
+    tracing::info!("Creating pylock.toml file");
+    fs::write(&target, contents).map_err(|e| {
+        miette::miette!("Could not write pylock file '{}': {}", target.display(), e)
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::project::export::test_utils::{pypi_package_with_url, SHA256};
+    use rattler_digest::{parse_digest_from_hex, Sha256};
+    use rattler_lock::LockFile;
+    use std::path::Path;
+
+    #[test]
+    fn test_render_pylock() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/cli/project/export/test-data/testenv/pixi.lock");
+        let lock_file = LockFile::from_path(&path).unwrap();
+        let packages = lock_file
+            .environment("with-pypi")
+            .unwrap()
+            .packages(Platform::Linux64)
+            .unwrap()
+            .filter_map(|p| p.as_pypi().map(|p| p.data().package.clone()))
+            .collect::<Vec<_>>();
+
+        let contents = render_pylock(&packages).unwrap();
+        let document = contents.parse::<DocumentMut>().unwrap();
+        assert_eq!(document["lock-version"].as_str(), Some("1.0"));
+
+        let six = &document["packages"][0];
+        assert_eq!(six["name"].as_str(), Some("six"));
+        assert_eq!(six["version"].as_str(), Some("1.16.0"));
+        assert_eq!(
+            six["wheels"][0]["name"].as_str(),
+            Some("six-1.16.0-py2.py3-none-any.whl")
+        );
+        assert!(six["wheels"][0]["url"]
+            .as_str()
+            .unwrap()
+            .starts_with("https://files.pythonhosted.org/"));
+        assert_eq!(
+            six["wheels"][0]["hashes"]["sha256"].as_str(),
+            Some("8abb2f1d86890a2dfb989f9a77cfcfd3e47c2a354b01111771326f8aa26e0254")
+        );
+        assert!(contents.contains("[[packages.wheels]]"), "{contents}");
+
+        // Wheels and sources without a hash are rejected
+        let mut unhashed = packages[0].clone();
+        unhashed.hash = None;
+        assert!(render_pylock(&[unhashed]).is_err());
+    }
+
+    #[test]
+    fn test_package_source() {
+        let source = |url_or_path, editable| {
+            let sha256 = parse_digest_from_hex::<Sha256>(SHA256).unwrap();
+            let package =
+                pypi_package_with_url(url_or_path, Some(PackageHashes::Sha256(sha256)), editable);
+            package_source(&package).map(|(key, item)| (key, item.to_string()))
+        };
+
+        let (key, table) = source(UrlOrPath::Path(PathBuf::from("pkgs/six")), true).unwrap();
+        assert_eq!(key, "directory");
+        assert!(table.contains("editable = true"), "{table}");
+
+        let archive = PathBuf::from("dist/six-1.16.0.tar.gz");
+        let (key, table) = source(UrlOrPath::Path(archive), false).unwrap();
+        assert_eq!(key, "archive");
+        assert!(table.contains(SHA256), "{table}");
+
+        let sdist = Url::parse("direct+https://example.com/six-1.16.0.tar.gz").unwrap();
+        let (key, table) = source(UrlOrPath::Url(sdist), false).unwrap();
+        assert_eq!(key, "sdist");
+        assert!(
+            table.contains("url = \"https://example.com/six-1.16.0.tar.gz\""),
+            "{table}"
+        );
+
+        let git =
+            Url::parse("git+https://github.com/benjaminp/six.git?rev=1.16.0#65486e4").unwrap();
+        let (key, table) = source(UrlOrPath::Url(git), false).unwrap();
+        assert_eq!(key, "vcs");
+        assert!(
+            table.contains("url = \"https://github.com/benjaminp/six.git\""),
+            "{table}"
+        );
+        assert!(table.contains("commit-id = \"65486e4\""), "{table}");
+
+        // A git url has to be pinned to a commit
+        let unpinned = Url::parse("git+https://github.com/benjaminp/six.git").unwrap();
+        assert!(source(UrlOrPath::Url(unpinned), false).is_err());
+    }
+}
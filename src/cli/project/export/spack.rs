@@ -0,0 +1,115 @@
+use std::fs;
+use std::path::PathBuf;
+
+use super::conda_explicit_spec::{channel_of_entry, cwd, locked_platform_packages};
+use crate::cli::cli_config::PrefixUpdateConfig;
+use crate::Project;
+use clap::Parser;
+use rattler_conda_types::Platform;
+use rattler_lock::CondaPackage;
+
+#[derive(Debug, Parser)]
+#[clap(arg_required_else_help = false)]
+pub struct Args {
+    /// The directory to write the `spack-{environment}.yaml` file to. Defaults
+    /// to the current working directory.
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+
+    /// The platform to render. Defaults to the only platform of the
+    /// environment in the lock file, or the current platform.
+    #[arg(long)]
+    pub platform: Option<Platform>,
+
+    /// The environment to render. Defaults to the default environment.
+    #[arg(short, long)]
+    pub environment: Option<String>,
+
+    #[clap(flatten)]
+    pub prefix_update_config: PrefixUpdateConfig,
+}
+
+/// Renders the conda packages as the specs of a `spack.yaml`. Every package is
+/// written as `name@version` with a comment noting the build string and the
+/// channel, so the file documents the environment but is not meant to be
+/// installed by spack.
+fn render_spack_yaml<'a>(conda_packages: impl IntoIterator<Item = &'a CondaPackage>) -> String {
+    let mut spack = String::from("# Generated by `pixi project export spack`\nspack:\n  specs:\n");
+    for package in conda_packages {
+        let record = package.package_record();
+        spack.push_str(&format!(
+            "  - {}@{}  # build: {}, channel: {}\n",
+            record.name.as_normalized(),
+            record.version,
+            record.build,
+            channel_of_entry(package.url().as_str())
+        ));
+    }
+    spack
+}
+
+pub async fn execute(project: Project, args: Args) -> miette::Result<()> {
+    let environment = project.environment_from_name_or_env_var(args.environment)?;
+    let lock_file = super::load_lock_file(
+        &project,
+        args.prefix_update_config.lock_file_usage(),
+        args.prefix_update_config.no_install(),
+        0,
+    )
+    .await?;
+
+    let platform = args
+        .platform
+        .unwrap_or_else(|| super::default_platform(&lock_file, &environment));
+    let locked_deps = locked_platform_packages(&lock_file, &environment, platform)?;
+
+    let conda_packages = locked_deps
+        .into_iter()
+        .filter_map(|p| p.as_conda().cloned())
+        .collect::<Vec<_>>();
+    let contents = render_spack_yaml(&conda_packages);
+
+    let output_dir = match args.output_dir {
+        Some(output_dir) => output_dir,
+        None => cwd()?,
+    };
+    let target = output_dir.join(format!("spack-{}.yaml", environment.name().as_str()));
+
+    tracing::info!("Creating spack environment file");
+    fs::write(&target, contents)
+        .map_err(|e| miette::miette!("Could not write spack file '{}': {}", target.display(), e))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rattler_lock::LockFile;
+    use std::path::Path;
+
+    #[test]
+    fn test_render_spack_yaml() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("src/cli/project/export/test-data/testenv/pixi.lock");
+        let lock_file = LockFile::from_path(&path).unwrap();
+        let packages = lock_file
+            .environment("default")
+            .unwrap()
+            .packages(Platform::Linux64)
+            .unwrap()
+            .filter_map(|p| p.as_conda().cloned())
+            .collect::<Vec<_>>();
+
+        let spack = render_spack_yaml(&packages);
+        assert!(spack.starts_with("# Generated by `pixi project export spack`\nspack:\n  specs:\n"));
+        assert!(spack.contains(
+            "  - bzip2@1.0.8  # build: h4bc722e_7, channel: https://conda.anaconda.org/conda-forge\n"
+        ));
+        assert_eq!(spack.lines().filter(|l| l.starts_with("  - ")).count(), 4);
+
+        // The rendered specs are valid yaml
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&spack).unwrap();
+        assert_eq!(yaml["spack"]["specs"][1], "bzip2@1.0.8");
+    }
+}
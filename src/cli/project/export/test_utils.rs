@@ -0,0 +1,99 @@
+//! Builders for synthetic locked packages, so the exporters can be tested
+//! without a lock file of a real project.
+
+use std::str::FromStr;
+
+use pep440_rs::Version;
+use rattler_conda_types::Platform;
+use rattler_lock::{CondaPackage, LockFile, PackageHashes, PypiPackageData, UrlOrPath};
+
+pub(super) const MD5: &str = "62ee74e96c5ebb0af99386de58cf9553";
+pub(super) const SHA256: &str = "5ced96500d945fb286c9c838e54fa759aa04a7129c59800f0846b4335cee770d";
+
+/// The pip requirements line of the `six` wheel that is locked in the
+/// `with-pypi` environment of the test project.
+pub(super) const SIX_REQUIREMENT: &str = "https://files.pythonhosted.org/packages/d9/5a/e7c31adbe875f2abbb91bd84cf2dc52d792b5a01506781dbcf25c91daf11/six-1.16.0-py2.py3-none-any.whl \
+    ; python_version >= \"2.7\" and python_version != \"3.0.*\" and python_version != \"3.1.*\" and python_version != \"3.2.*\" \
+    --hash=sha256:8abb2f1d86890a2dfb989f9a77cfcfd3e47c2a354b01111771326f8aa26e0254\n";
+
+/// Builds a lock file containing a single `foo` package with the given hashes
+/// and returns that package.
+pub(super) fn conda_package_with_hashes(md5: Option<&str>, sha256: Option<&str>) -> CondaPackage {
+    conda_package_with_url(
+        "https://conda.anaconda.org/conda-forge/linux-64/foo-1.0-h123_0.conda",
+        md5,
+        sha256,
+    )
+}
+
+/// Builds a lock file containing a single `foo` package with the given url
+/// and hashes and returns that package.
+pub(super) fn conda_package_with_url(
+    url: &str,
+    md5: Option<&str>,
+    sha256: Option<&str>,
+) -> CondaPackage {
+    let mut lock_file = format!(
+        "version: 5
+environments:
+  default:
+    channels:
+    - url: https://conda.anaconda.org/conda-forge/
+    packages:
+      linux-64:
+      - conda: {url}
+packages:
+- kind: conda
+  name: foo
+  version: '1.0'
+  build: h123_0
+  subdir: linux-64
+  url: {url}
+"
+    );
+    if let Some(sha256) = sha256 {
+        lock_file.push_str(&format!("  sha256: {sha256}\n"));
+    }
+    if let Some(md5) = md5 {
+        lock_file.push_str(&format!("  md5: {md5}\n"));
+    }
+
+    LockFile::from_str(&lock_file)
+        .unwrap()
+        .environment("default")
+        .unwrap()
+        .packages(Platform::Linux64)
+        .unwrap()
+        .find_map(|p| p.as_conda().cloned())
+        .unwrap()
+}
+
+/// Returns the `six` wheel from PyPI with the given hash.
+pub(super) fn pypi_package_with_hashes(hash: Option<PackageHashes>) -> PypiPackageData {
+    pypi_package_with_url(
+        UrlOrPath::Url(
+            "https://files.pythonhosted.org/packages/six-1.16.0-py2.py3-none-any.whl"
+                .parse()
+                .unwrap(),
+        ),
+        hash,
+        false,
+    )
+}
+
+/// Returns a `six` package that is installed from the given url or path.
+pub(super) fn pypi_package_with_url(
+    url_or_path: UrlOrPath,
+    hash: Option<PackageHashes>,
+    editable: bool,
+) -> PypiPackageData {
+    PypiPackageData {
+        name: "six".parse().unwrap(),
+        version: Version::from_str("1.16.0").unwrap(),
+        url_or_path,
+        hash,
+        requires_dist: vec![],
+        requires_python: None,
+        editable,
+    }
+}
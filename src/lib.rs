@@ -15,6 +15,8 @@ mod uv_reporter;
 mod repodata;
 mod rlimit;
 
+pub use cli::project::export::conda_explicit_spec::ExplicitSpecOptions;
+pub use environment::LockFileUsage;
 pub use lock_file::load_lock_file;
 pub use lock_file::UpdateLockFileOptions;
 pub use project::{DependencyType, Project};
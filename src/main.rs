@@ -1,7 +1,17 @@
 #[tokio::main]
 pub async fn main() {
     if let Err(err) = pixi::cli::execute().await {
-        eprintln!("{err:?}");
-        std::process::exit(1);
+        match err.downcast_ref::<pixi::cli::ExitCodeError>() {
+            Some(pixi::cli::ExitCodeError { code, report }) => {
+                if let Some(report) = report {
+                    eprintln!("{report:?}");
+                }
+                std::process::exit(*code);
+            }
+            None => {
+                eprintln!("{err:?}");
+                std::process::exit(1);
+            }
+        }
     }
 }
@@ -250,7 +250,7 @@ impl<'p> Environment<'p> {
     }
 
     /// Validates that the given platform is supported by this environment.
-    fn validate_platform_support(
+    pub(crate) fn validate_platform_support(
         &self,
         platform: Option<Platform>,
     ) -> Result<(), UnsupportedPlatformError> {
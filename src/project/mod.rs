@@ -398,14 +398,30 @@ impl Project {
     }
 
     /// Returns an environment in this project based on a name or an environment
-    /// variable.
+    /// variable. If the environment doesn't exist the error mentions when the
+    /// name was read from `PIXI_ENVIRONMENT_NAME`, e.g. in a `pixi shell`.
     pub(crate) fn environment_from_name_or_env_var(
         &self,
         name: Option<String>,
     ) -> miette::Result<Environment> {
+        let from_arg = name.is_some();
         let environment_name = EnvironmentName::from_arg_or_env_var(name).into_diagnostic()?;
-        self.environment(&environment_name)
-            .ok_or_else(|| miette::miette!("unknown environment '{environment_name}'"))
+        self.environment(&environment_name).ok_or_else(|| {
+            // The default environment always exists, so without an argument
+            // the name must come from the environment variable
+            if from_arg {
+                miette::miette!(
+                    code = "unknown-environment",
+                    "unknown environment '{environment_name}'"
+                )
+            } else {
+                miette::miette!(
+                    code = "unknown-environment",
+                    help = "pass `--environment` to select an environment of this project, or leave the shell of the other project",
+                    "unknown environment '{environment_name}', read from the environment variable PIXI_ENVIRONMENT_NAME={environment_name}"
+                )
+            }
+        })
     }
 
     /// Get or initialize the activated environment variables